@@ -2,13 +2,21 @@
 
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use parking_lot::Mutex;
+use typst::diag::SourceDiagnostic;
 use typst::foundations::{Capturer, IntoValue};
-use typst::foundations::{Dict, Value};
+use typst::foundations::{sys, Dict, Module, Value};
+use typst::model::{HeadingElem, Numbering, NumberingPattern};
+use typst::text::{Lang, Region, TextElem};
 use typst::visualize::Color;
-use typst::LibraryBuilder;
+use typst::{Library, LibraryBuilder};
+use typst_pdf::{PdfStandard, PdfStandards};
+use typst_syntax::package::PackageSpec;
 use typst_syntax::{FileId, Source, Span, VirtualPath};
 use typst_utils::LazyHash;
 
@@ -16,8 +24,26 @@ use crate::compiler::Compiler;
 use crate::errors::{WrapperError, WrapperResult};
 use crate::files::LazyFile;
 use crate::fonts::FontCache;
-use crate::package::create_http_agent;
-use crate::parameters::Input;
+use crate::package::{create_http_agent, DownloadProgress, PackageSettings};
+use crate::parameters::{DatetimePrecision, Input};
+
+/// Entry name of the content input, also used as its virtual path.
+const CONTENT_ENTRY: &str = "<content>";
+
+/// Path the entry wrapper includes the content input by (typst can include files only
+/// by path, the fake id of the content input can't be reached).
+///
+/// It can't clash with a real file: NUL character isn't allowed in file names on any
+/// supported platform, so no file in the root has this path and a typst file can only
+/// reach it by spelling out `\u{0}` on purpose.
+const WRAPPED_CONTENT_ENTRY: &str = "/\0<content>";
+
+/// Default producer of the compiled documents.
+///
+/// Typst version has to follow the `typst` dependency in `Cargo.toml` (checked by a test).
+const DEFAULT_PRODUCER: &str = concat!(
+    "typst-lib-wrapper/", env!("CARGO_PKG_VERSION"), " (typst 0.12.0)"
+);
 
 /// [Compiler] factory, which can be used in order to configure the properties \
 /// of a new [Compiler].
@@ -28,14 +54,44 @@ use crate::parameters::Input;
 ///
 /// Available configurations:
 /// - `input`: Compilation [Input] (File or String).
+/// - `additional_roots`: Trusted directories used to resolve files outside of the project root.
+/// - `virtual_files`: In-memory files, used instead of the files on disk.
 /// - `sys_inputs`: Provides data to `sys.inputs` dictionary.
 /// - `custom_data`: Overrides typst standard library with custom symbol definitions.
+/// - `strict_custom_data`: Rejects custom data keys without `_` prefix or shadowing symbols.
+/// - `modules`: Binds typst modules into the global scope, documents import them by name.
+/// - `library`: Replaces typst standard library with a custom [Library].
+/// - `page_number_offset`: Offsets the page counter, so numbering continues from a previous part.
+/// - `producer`: Tool that produced the document, written to PDF metadata and SVG comment.
+/// - `heading_numbering`: Default heading numbering pattern, applied to every heading.
+/// - `locale`: Default text language and region (quotes, hyphenation, built-in terms).
+/// - `deny_warnings`: Treats compilation warnings as errors.
+/// - `pdf_standards`: PDF standards to conform to when compiling to PDF, ignored otherwise.
+/// - `pdf_ident`: Stable PDF document identifier, for reproducible PDF output.
+/// - `pdf_timestamp`: Fixed (or omitted) PDF creation timestamp, for reproducible PDF output.
+/// - `now`: Fixed current time, used by `datetime.today()` and as the default PDF timestamp.
+/// - `utc_dates`: Uses UTC instead of the local time zone for `datetime.today()`.
+/// - `datetime_precision`: Whether `datetime.today()` includes the time of day.
+/// - `full_font_embedding`: Requests fonts embedded without subsetting (not supported yet).
 /// - `font_paths`: If needed, additional font paths, will be inserted into [FontCache].
 /// - `ppi`: Pixels per inch when compiling to PNG, ignored otherwise.
-/// - `background`: Backgroud color when compiling to PNG, ignored otherwise.
+/// - `background`: Backgroud color when compiling to PNG or SVG, ignored otherwise.
+/// - `svg_background`: Overrides the background color (or transparency) of SVG pages.
+/// - `svg_responsive`: Drops fixed SVG dimensions, so SVGs scale with their container.
+/// - `autocrop`: Trims background margins of rendered PNG pages, ignored otherwise.
+/// - `autocrop_padding`: Pixels of background left around the content when trimming.
 /// - `agent`: Overrides default [ureq::Agent] with provided one.
+/// - `network_timeout`: Timeout of package downloads with the default [ureq::Agent].
+/// - `download_retries`: Retries of package downloads that failed with a transient error.
+/// - `max_extracted_size`: Limits total size of files extracted from a downloaded package.
+/// - `request_deadline`: Point in time after which package downloads are aborted.
+/// - `package_registries`: Hosts packages in custom namespaces are downloaded from.
+/// - `offline`: Never downloads packages, only locally available packages are used.
+/// - `package_cache_dir`: Directory packages are looked up in and downloaded to.
+/// - `download_progress`: Callback reporting package download progress.
+/// - `package_checksums`: Expected SHA-256 checksums of downloaded package archives.
 ///
-///  `add_` methods exists for `sys_inputs`, `custom_data` and `font_paths`. \
+///  `add_` methods exists for `sys_inputs`, `custom_data`, `virtual_files` and `font_paths`. \
 /// They are used if you wish to add items one by one (extending vector) without rebuilding.
 ///
 /// # Note / Warning
@@ -55,13 +111,6 @@ use crate::parameters::Input;
 /// all fonts are lazily loaded into memory, but they stay there, so **manually empty**
 /// the [FontCache].
 ///
-/// ### Filename restrictions
-/// Do not use any filenames or paths that contain text
-/// **`"CUSTOM_SOURCE_CONTENT_INPUT_IN_MEMORY_FILE"`**. \
-/// For more information check the main ReadMe file.
-///
-/// **⚠ You have been warned ⚠**
-///
 /// # Examples
 /// ## Compiling PDF
 /// Shows how to compile existing typst file to PDF. Saves the result to disk.
@@ -116,13 +165,45 @@ use crate::parameters::Input;
 pub struct CompilerBuilder {
     /// Compilation [Input] (File or String).
     input: Input,
+    /// Additional trusted roots, used to resolve files not found in the project root.
+    additional_roots: Vec<PathBuf>,
+    /// In-memory files, keyed by their path within the project root.
+    virtual_files: HashMap<PathBuf, Vec<u8>>,
 
     /// Provides data to `sys.inputs` dictionary.
     sys_inputs: Vec<(String, String)>,
     /// Overrides typst standard library with custom symbol definitions.
     custom_data: Vec<(String, Value)>,
-    /// Generate PDF/A output. Only used if compiler compiles to PDF.
-    pdf_a: Option<bool>,
+    /// Optional strict custom data keys check.
+    strict_custom_data: Option<bool>,
+    /// Typst modules bound into the global scope, with their names.
+    modules: Vec<(String, Module)>,
+    /// Optional custom standard library.
+    library: Option<Library>,
+    /// PDF standards to conform to. Only used if compiler compiles to PDF.
+    pdf_standards: Option<Vec<PdfStandard>>,
+    /// Optional stable PDF document identifier.
+    pdf_ident: Option<String>,
+    /// Optional PDF timestamp override, inner `None` omits the timestamp.
+    pdf_timestamp: Option<Option<DateTime<Utc>>>,
+    /// Optional current time override.
+    now: Option<DateTime<Utc>>,
+    /// Optional UTC (instead of local time zone) `datetime.today()`.
+    utc_dates: Option<bool>,
+    /// Optional `datetime.today()` precision.
+    datetime_precision: Option<DatetimePrecision>,
+    /// Optional full font embedding request.
+    full_font_embedding: Option<bool>,
+    /// Optional page counter offset.
+    page_number_offset: Option<usize>,
+    /// Optional producer of the compiled documents.
+    producer: Option<String>,
+    /// Optional default heading numbering pattern.
+    heading_numbering: Option<String>,
+    /// Optional default locale (language and region).
+    locale: Option<String>,
+    /// Optional warnings-as-errors mode.
+    deny_warnings: Option<bool>,
 
     /// If needed, additional font paths, will be inserted into [FontCache].
     font_paths: Vec<PathBuf>,
@@ -130,8 +211,34 @@ pub struct CompilerBuilder {
     ppi: Option<f32>,
     /// Optional PNG background [Color].
     background: Option<Color>,
+    /// Optional SVG background [Color], inner `None` is transparent.
+    svg_background: Option<Option<Color>>,
+    /// Optional responsive SVG output.
+    svg_responsive: Option<bool>,
+    /// Optional PNG background trimming.
+    autocrop: Option<bool>,
+    /// Optional PNG trimming padding in pixels.
+    autocrop_padding: Option<u32>,
     /// Optional [ureq::Agent].
-    agent: Option<ureq::Agent>
+    agent: Option<ureq::Agent>,
+    /// Optional network timeout of the default [ureq::Agent].
+    network_timeout: Option<Duration>,
+    /// Optional number of package download retries and the initial backoff.
+    download_retries: Option<(u32, Duration)>,
+    /// Optional maximum extracted package size in bytes.
+    max_extracted_size: Option<u64>,
+    /// Optional package download deadline.
+    request_deadline: Option<Instant>,
+    /// Package registry hosts keyed by namespace.
+    package_registries: HashMap<String, String>,
+    /// Optional offline mode, packages are never downloaded.
+    offline: Option<bool>,
+    /// Optional package cache directory.
+    package_cache_dir: Option<PathBuf>,
+    /// Optional package download progress callback.
+    download_progress: Option<DownloadProgress>,
+    /// Expected SHA-256 checksums of downloaded package archives.
+    package_checksums: HashMap<PackageSpec, String>
 }
 
 impl CompilerBuilder {
@@ -146,23 +253,47 @@ impl CompilerBuilder {
     ///     .build()
     ///     .expect("Couldn't build the compiler");
     /// ```
-    ///
-    /// # Note / Warning
-    /// Do not use any filenames or paths that contain text
-    /// **`"CUSTOM_SOURCE_CONTENT_INPUT_IN_MEMORY_FILE"`**. \
-    /// For more information check the main ReadMe file.
     pub fn with_input(input: Input) -> Self {
         Self {
             input,
+            additional_roots: Vec::new(),
+            virtual_files: HashMap::new(),
 
             sys_inputs: Vec::new(),
             custom_data: Vec::new(),
-            pdf_a: Some(false),
+            strict_custom_data: None,
+            modules: Vec::new(),
+            library: None,
+            pdf_standards: None,
+            pdf_ident: None,
+            pdf_timestamp: None,
+            now: None,
+            utc_dates: None,
+            datetime_precision: None,
+            full_font_embedding: None,
+            page_number_offset: None,
+            producer: None,
+            heading_numbering: None,
+            locale: None,
+            deny_warnings: None,
 
             font_paths: Vec::new(),
             ppi: None,
             background: None,
-            agent: None
+            svg_background: None,
+            svg_responsive: None,
+            autocrop: None,
+            autocrop_padding: None,
+            agent: None,
+            network_timeout: None,
+            download_retries: None,
+            max_extracted_size: None,
+            request_deadline: None,
+            package_registries: HashMap::new(),
+            offline: None,
+            package_cache_dir: None,
+            download_progress: None,
+            package_checksums: HashMap::new()
         }
     }
 
@@ -177,11 +308,6 @@ impl CompilerBuilder {
     ///     .build()
     ///     .expect("Couldn't build the compiler");
     /// ```
-    ///
-    /// # Note / Warning
-    /// Do not use any filenames or paths that contain text
-    /// **`"CUSTOM_SOURCE_CONTENT_INPUT_IN_MEMORY_FILE"`**. \
-    /// For more information check the main ReadMe file.
     pub fn with_file_input(entry: impl ToString, root: impl Into<PathBuf>) -> Self {
         let input = Input::File { entry: entry.to_string(), root: root.into() };
         return Self::with_input(input);
@@ -370,6 +496,26 @@ impl CompilerBuilder {
     ///     .build()
     ///     .expect("Couldn't build the compiler");
     /// ```
+    ///
+    /// # Example
+    /// Passes runtime-generated binary data (for example an image) as [Bytes](
+    /// typst::foundations::Bytes) value, without writing a temporary file. \
+    /// Mind that [Vec\<u8\>](Vec) is converted to typst **array** of integers,
+    /// convert it to [Bytes](typst::foundations::Bytes) first.
+    /// ```
+    /// use typst_lib_wrapper::reexports::Bytes;
+    ///
+    /// let content = r##"
+    ///     #image.decode(_LOGO, width: 4cm)
+    /// "##;
+    ///
+    /// let png: Vec<u8> = std::fs::read("./logo.png").expect("Couldn't read PNG");
+    ///
+    /// let compiler = CompilerBuilder::with_content_input(content)
+    ///     .add_custom_data_one(("_LOGO", Bytes::from(png)))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
     pub fn add_custom_data_one(mut self, custom_data: (impl ToString, impl IntoValue)) -> Self {
         self.custom_data.push((custom_data.0.to_string(), custom_data.1.into_value()));
         self
@@ -421,6 +567,157 @@ impl CompilerBuilder {
         self
     }
 
+    /// ## Strict custom data
+    /// Default value: false
+    ///
+    /// Enforces the recommended custom data naming. [build](Self::build) fails with
+    /// [WrapperError::InvalidCustomDataKey] if a custom data key doesn't start with an
+    /// underscore `"_"`, or with [WrapperError::ShadowedStandardSymbol] if it overwrites
+    /// a standard library symbol (like `text` or `page`). Doesn't apply to
+    /// [modules](Self::with_module).
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let result = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_strict_custom_data(true)
+    ///     .add_custom_data_one(("text", "Oops"))
+    ///     .build();
+    ///
+    /// assert!(result.is_err());
+    /// ```
+    pub fn with_strict_custom_data(mut self, strict: bool) -> Self {
+        self.strict_custom_data = Some(strict);
+        self
+    }
+
+    /// Binds a typst [Module] into the global scope under the `name`, so documents can
+    /// import its definitions with `#import name: *` (or use them as `name.definition`).
+    /// Can be called multiple times, a later module with the same `name` replaces the
+    /// earlier one.
+    ///
+    /// Structured alternative to [custom data](Self::with_custom_data), definitions stay in
+    /// the module namespace and don't collide with the standard library symbols. Modules are
+    /// bound before custom data, so custom data with the same key overrides the module.
+    ///
+    /// # Example
+    /// ```
+    /// use typst_lib_wrapper::reexports::{Module, Scope};
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.define("company", "ACME");
+    /// scope.define("year", 2024);
+    /// let helpers = Module::new("helpers", scope);
+    ///
+    /// let content = r##"
+    ///     #import helpers: *
+    ///     (c) #year #company
+    /// "##;
+    ///
+    /// let compiler = CompilerBuilder::with_content_input(content)
+    ///     .with_module("helpers", helpers)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_module(mut self, name: impl ToString, module: Module) -> Self {
+        self.modules.push((name.to_string(), module));
+        self
+    }
+
+    /// ## Library
+    /// Default value: typst standard library
+    ///
+    /// Replaces the typst standard library with a fully customized [Library], for example
+    /// one with removed or replaced definitions, or custom default styles.
+    ///
+    /// `sys_inputs`, if any, replace the `sys` module of the provided library. `custom_data`,
+    /// `heading_numbering` and `locale` still apply on top of the provided library.
+    ///
+    /// # Example
+    /// Removes the `image` function from the standard library.
+    /// ```
+    /// use typst_lib_wrapper::reexports::{Library, Scope};
+    ///
+    /// let mut library = Library::default();
+    /// let mut global = Scope::new();
+    /// for (name, value, _) in library.global.scope().iter() {
+    ///     if name != "image" {
+    ///         global.define(name.clone(), value.clone());
+    ///     }
+    /// }
+    /// library.global = library.global.with_scope(global);
+    ///
+    /// let compiler = CompilerBuilder::with_content_input("#image(\"logo.png\")")
+    ///     .with_library(library)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_library(mut self, library: Library) -> Self {
+        self.library = Some(library);
+        self
+    }
+
+    /// Provides a way to resolve files outside of the project root, from additional
+    /// **trusted** root directories (like a shared templates/assets directory).
+    ///
+    /// Typst paths can't escape the root, `"../shared/logo.png"` imported from the root
+    /// resolves to `"/shared/logo.png"`. File is first looked up in the project root, then
+    /// in each additional root (in order). The first root that contains the file is used.
+    /// If none of them contain it, the error is reported for the project root.
+    ///
+    /// Entry file still has to be inside the project root. Roots are canonicalized,
+    /// [build](Self::build) fails with [WrapperError::InputNotFound] if a root doesn't exist.
+    ///
+    /// # Note / Warning
+    /// Documents can read **any** file inside additional roots. Use only with trusted inputs.
+    ///
+    /// # Example
+    /// Project in `./monorepo/report` imports `"../shared/template.typ"`, which
+    /// resolves to `./monorepo/shared/template.typ`.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./monorepo/report";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_additional_roots(vec!["./monorepo"])
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_additional_roots(mut self, roots: Vec<impl Into<PathBuf>>) -> Self {
+        self.additional_roots = roots.into_iter().map(|x| x.into()).collect();
+        self
+    }
+
+    /// Adds an in-memory (virtual) file with `bytes` at `path`, relative to the project root.
+    ///
+    /// Virtual files can be imported, included and read (`#image`, `#read`, ...) just like
+    /// files on disk, so a whole multi-file project can be compiled from memory (for example
+    /// together with [Input::Content]). Virtual files take precedence over the files on disk
+    /// with the same path, adding a file with the same path again replaces it.
+    ///
+    /// # Example
+    /// Compiles content that imports a template and reads an image, all from memory.
+    /// ```
+    /// let content = r##"
+    ///     #import "template.typ": title
+    ///     #title[Report]
+    ///     #image("images/logo.svg")
+    /// "##;
+    /// let logo: Vec<u8> = std::fs::read("./logo.svg").expect("Couldn't read logo");
+    ///
+    /// let compiler = CompilerBuilder::with_input(Input::content(content))
+    ///     .add_virtual_file("template.typ", b"#let title(body) = heading(body)".to_vec())
+    ///     .add_virtual_file("images/logo.svg", logo)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn add_virtual_file(mut self, path: impl Into<PathBuf>, bytes: Vec<u8>) -> Self {
+        self.virtual_files.insert(path.into(), bytes);
+        self
+    }
+
     /// Provides a way to add additional fonts to the [FontCache].
     ///
     /// # Note / Warning
@@ -454,6 +751,17 @@ impl CompilerBuilder {
     ///     .build()
     ///     .expect("Couldn't build the compiler");
     /// ```
+    ///
+    /// [build](Self::build) fails with [WrapperError::FontLoadingError] if a font file can't
+    /// be read, or with [WrapperError::FontFaceLoadingError] if it doesn't contain any font.
+    /// ```
+    /// let result = CompilerBuilder::with_content_input("Hello")
+    ///     .with_font_paths(vec!["./fonts/missing.ttf"])
+    ///     .build();
+    ///
+    /// assert!(matches!(result, Err(WrapperError::FontLoadingError(path, _))
+    ///     if path.ends_with("missing.ttf")));
+    /// ```
     pub fn with_font_paths(mut self, font_paths: Vec<impl Into<PathBuf>>) -> Self {
         self.font_paths = font_paths.into_iter().map(|x| x.into()).collect();
         self
@@ -548,155 +856,1028 @@ impl CompilerBuilder {
     /// ```
     /// Color::from_u8(0, 0, 0, 0)
     /// ```
+    /// Also used as SVG page background, unless overridden with
+    /// [with_svg_background](Self::with_svg_background).
+    ///
     /// # Note
-    /// Ignored if not compiling to PNG.
+    /// Ignored if not compiling to PNG or SVG.
     pub fn with_background(mut self, color: Color) -> Self {
         self.background = Some(color);
         self
     }
 
-    /// ## PDF/A output
+    /// ## SVG background [Color]
+    /// Default value: same as [with_background](Self::with_background)
+    ///
+    /// Background of SVG pages, independent of the PNG background. `None` creates
+    /// transparent SVGs (pages have no background rectangle).
+    ///
+    /// # Note
+    /// Ignored if not compiling to SVG.
+    ///
+    /// # Example
+    /// Transparent SVGs, white PNGs.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_background(Color::WHITE)
+    ///     .with_svg_background(None)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_svg_background(mut self, color: Option<Color>) -> Self {
+        self.svg_background = Some(color);
+        self
+    }
+
+    /// ## Responsive SVG
     /// Default value: false
     ///
-    /// Enables creation of PDF/A files.
+    /// Removes the fixed `width` and `height` (in points) from the root element of every
+    /// SVG page and keeps only the `viewBox` (in points), so the SVG scales fluidly with
+    /// its container while keeping the aspect ratio.
     ///
     /// # Note
-    /// Ignored if not compiling to PDF.
-    pub fn with_pdf_a(mut self, pdf_a: bool) -> Self {
-        self.pdf_a = Some(pdf_a);
+    /// Ignored if not compiling to SVG.
+    pub fn with_svg_responsive(mut self, responsive: bool) -> Self {
+        self.svg_responsive = Some(responsive);
         self
     }
 
-    /// Optional [ureq::Agent]
+    /// ## Automatic cropping
+    /// Default value: false
     ///
-    /// Used for downloading packages from the repository. Primarily exists to enable loading
-    /// custom TLS configuration.
+    /// Trims rows and columns that are entirely the background [Color] from every
+    /// rendered page, leaving just the content (and optional padding, see
+    /// [with_autocrop_padding](Self::with_autocrop_padding)). Blank pages are left as is.
+    ///
+    /// Handy for rendering logos and small snippets without page margins.
     ///
     /// # Example
-    /// How to create [ureq::Agent].
     /// ```
-    /// let agent = ureq::AgentBuilder::new().build();
-    ///
     /// let content = r##"
     ///     #set page(paper: "a4");
-    ///     = Hello World
-    ///     Hello world from typst.
+    ///     $ sum_(k=1)^n k = (n(n+1)) / 2 $
     /// "##;
     ///
     /// let compiler = CompilerBuilder::with_content_input(content)
-    ///     .with_agent(agent)
+    ///     .with_autocrop(true)
+    ///     .with_autocrop_padding(8)
     ///     .build()
     ///     .expect("Couldn't build the compiler");
     /// ```
-    pub fn with_agent(mut self, agent: ureq::Agent) -> Self {
-        self.agent = Some(agent);
+    /// # Note
+    /// Ignored if not compiling to PNG.
+    pub fn with_autocrop(mut self, autocrop: bool) -> Self {
+        self.autocrop = Some(autocrop);
         self
     }
 
-    /// Finalizes the configuration and takes ownership of the [CompilerBuilder].
-    /// Returns an error if something goes wrong.
+    /// ## Automatic cropping padding
+    /// Default value: 0
     ///
-    /// # Note / Warning
-    /// ### Blocking Mutex
-    /// It is recommended to add fonts using [FontCache] to avoid locking the font mutex
-    /// for too long. This mutex is **NOT ASYNC** so keep that in mind. Use **'blocking task'**
-    /// if you wish to compile documents in an async environment. Also, if compiling with an
-    /// opt-in feature (`"parallel_compilation"`) to PNGs or SVGs, the compiler tries to
-    /// encode/convert images to bytes in parallel with `rayon`. To sync up compiled pages,
-    /// again it uses **SYNC** mutex. \
-    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    /// Number of background pixels left around the content when automatic cropping
+    /// is enabled. Padding can't extend past the page edges.
     ///
-    /// ### [FontCache] cloning
-    /// Before building the compiler, builder locks [FontCache] and **clones** it for itself.
-    /// To reduce [FontCache] size it is recommended to avoid loading all system fonts and
-    /// to load only needed fonts. In practise this won't be that big of a deal, because
-    /// all fonts are lazily loaded into memory, but they stay there, so **manually empty**
-    /// the [FontCache].
+    /// # Note
+    /// Ignored if not compiling to PNG or if automatic cropping is disabled.
+    pub fn with_autocrop_padding(mut self, padding_px: u32) -> Self {
+        self.autocrop_padding = Some(padding_px);
+        self
+    }
+
+    /// ## PDF/A output
+    /// Default value: false
     ///
-    /// ### Filename restrictions
-    /// Do not use any filenames or paths that contain text
-    /// **`"CUSTOM_SOURCE_CONTENT_INPUT_IN_MEMORY_FILE"`**. \
-    /// For more information check the main ReadMe file.
+    /// Enables creation of PDF/A files (PDF/A-2b). Shorthand for [with_pdf_standards](
+    /// Self::with_pdf_standards), `true` selects `PdfStandard::A_2b`, `false` selects
+    /// `PdfStandard::V_1_7`. Overrides previously configured standards.
     ///
-    /// **⚠ You have been warned ⚠**
-    pub fn build(self) -> WrapperResult<Compiler> {
+    /// # Note
+    /// Ignored if not compiling to PDF.
+    pub fn with_pdf_a(mut self, pdf_a: bool) -> Self {
+        let standard = if pdf_a { PdfStandard::A_2b } else { PdfStandard::V_1_7 };
+        self.pdf_standards = Some(vec![standard]);
+        self
+    }
 
-        // Prevents forbidden filename/path input.
-        if self.input.is_forbidden() {
-            return Err(WrapperError::ForbiddenFilenamePathText);
-        }
+    /// ## PDF standards
+    /// Default value: `[PdfStandard::V_1_7]`
+    ///
+    /// PDF standards the output has to conform to, passed directly to typst PDF exporter.
+    /// Overrides previously configured standards (including [with_pdf_a](Self::with_pdf_a)).
+    ///
+    /// Supported standards depend on the bundled `typst-pdf` version, typst 0.12 supports
+    /// PDF 1.7 and PDF/A-2b only (other PDF/A levels like A-1b or A-3b aren't available yet).
+    /// Incompatible combination makes [build](Self::build) fail with
+    /// [WrapperError::InvalidPdfStandards].
+    ///
+    /// # Note
+    /// Ignored if not compiling to PDF.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "report.typ";
+    /// let root = "./archive";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_pdf_standards(vec![PdfStandard::A_2b])
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_pdf_standards(mut self, standards: Vec<PdfStandard>) -> Self {
+        self.pdf_standards = Some(standards);
+        self
+    }
 
-        let http_client = create_http_agent(self.agent);
+    /// ## PDF document identifier
+    /// Default value: automatic (typst derives it from the document title and authors)
+    ///
+    /// String that uniquely and **stably** identifies the document, for example a template
+    /// name with a record id. The hash of it is written as the PDF document ID
+    /// (the `ident` itself isn't leaked).
+    ///
+    /// Automatic identifier falls back to a hash of the PDF contents if the document doesn't
+    /// set both title and author, so it changes with every edit. Set the identifier if you
+    /// need the same ID across builds (byte-for-byte diffing, document tracking, ...).
+    ///
+    /// # Note
+    /// Ignored if not compiling to PDF.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "invoice.typ";
+    /// let root = "./templates";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_pdf_ident("invoice-2024-0042")
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_pdf_ident(mut self, ident: impl ToString) -> Self {
+        self.pdf_ident = Some(ident.to_string());
+        self
+    }
 
-        let now = chrono::Utc::now();
-        let ppi: f32 = self.ppi.unwrap_or(144.0); // default typst ppi: 144.0
-        let background = self.background.unwrap_or(Color::WHITE);
-        let mut files: HashMap<FileId, LazyFile> = HashMap::new();
+    /// ## PDF timestamp
+    /// Default value: compiler creation time
+    ///
+    /// Fixes the creation and modification date written to the PDF metadata. Combined with
+    /// [with_pdf_ident](Self::with_pdf_ident), the same input yields identical PDF bytes
+    /// across runs. Overrides [with_no_timestamp](Self::with_no_timestamp).
+    ///
+    /// Doesn't affect `datetime.today()` in the document.
+    ///
+    /// # Note
+    /// Ignored if not compiling to PDF.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let timestamp = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_timestamp(timestamp)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.pdf_timestamp = Some(Some(timestamp));
+        self
+    }
 
-        // Convert the input pairs to a dictionary.
-        let sys_inputs: Dict = self
-            .sys_inputs
-            .into_iter()
-            .map(|(key, value)| (key.into(), value.into_value()))
-            .collect();
-        let mut library = LibraryBuilder::default().with_inputs(sys_inputs).build();
+    /// Omits the creation and modification date from the PDF metadata entirely.
+    /// Overrides [with_timestamp](Self::with_timestamp).
+    ///
+    /// # Note
+    /// Ignored if not compiling to PDF.
+    pub fn with_no_timestamp(mut self) -> Self {
+        self.pdf_timestamp = Some(None);
+        self
+    }
 
-        // Provides a way to load custom data into the library, by overriding `keys`.
-        for (key, value) in self.custom_data.into_iter() {
+    /// ## Current time
+    /// Default value: compiler creation time
+    ///
+    /// Pins the current time of the compiler, so documents using `datetime.today()` are
+    /// reproducible (tests, CI, ...). `datetime.today(offset: ...)` is still relative to
+    /// this instant. Also used as the PDF timestamp, unless it's set with
+    /// [with_timestamp](Self::with_timestamp) or omitted with
+    /// [with_no_timestamp](Self::with_no_timestamp).
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let now = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_now(now)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_now(mut self, now: DateTime<Utc>) -> Self {
+        self.now = Some(now);
+        self
+    }
 
-            let key_eco = ecow::EcoString::from(key);
-            library
-                .global
-                .scope_mut()
-                .define_captured(key_eco, value, Capturer::Function, Span::detached());
-        }
+    /// ## UTC dates
+    /// Default value: false
+    ///
+    /// Uses UTC for `datetime.today()` (without an offset) instead of the local time zone
+    /// of the host, so servers in different regions produce the same dates.
+    /// `datetime.today(offset: ...)` is always relative to UTC and isn't affected.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_utc_dates(true)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_utc_dates(mut self, utc_dates: bool) -> Self {
+        self.utc_dates = Some(utc_dates);
+        self
+    }
 
-        let root_path: PathBuf;
-        let entry: Source = match self.input {
-            Input::Content(c) => {
-                root_path = PathBuf::from(".");
-                let vpath = VirtualPath::new(crate::RESERVED_IN_MEMORY_IDENTIFIER);
-                Source::new(FileId::new(None, vpath), c)
+    /// ## Datetime precision
+    /// Default value: [DatetimePrecision::DateOnly]
+    ///
+    /// Sets the precision of `datetime.today()`. With [DatetimePrecision::DateTime]
+    /// it also has the time of day, so documents can display e.g. the compile time
+    /// with `datetime.today().display("[hour]:[minute]:[second]")`.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_datetime_precision(DatetimePrecision::DateTime)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_datetime_precision(mut self, precision: DatetimePrecision) -> Self {
+        self.datetime_precision = Some(precision);
+        self
+    }
+
+    /// ## Full font embedding
+    /// Default value: false
+    ///
+    /// Requests fonts to be embedded in full, without subsetting. Some PDF editing tools
+    /// (for example Illustrator) can't add or change text using subsetted fonts.
+    ///
+    /// # Note / Warning
+    /// Typst 0.12 PDF exporter **always subsets** fonts (only used glyphs are embedded) and
+    /// doesn't provide a way to disable it. Until it does, enabling this option only adds
+    /// a warning to [build_warnings](Compiler::build_warnings), so the limitation doesn't go
+    /// unnoticed. Output is the same.
+    ///
+    /// Ignored if not compiling to PDF.
+    pub fn with_full_font_embedding(mut self, full_font_embedding: bool) -> Self {
+        self.full_font_embedding = Some(full_font_embedding);
+        self
+    }
+
+    /// ## Page number offset
+    /// Default value: 0
+    ///
+    /// Offsets the page counter, so the first page of the document is numbered
+    /// `offset + 1`. Useful when the document is compiled as a part of a larger
+    /// document (booklet), so it continues numbering where the previous part left off.
+    ///
+    /// The page counter is updated before the entry file is evaluated, so the
+    /// document can still update it on its own.
+    ///
+    /// # Example
+    /// Part 2 ended on page 10, so part 3 starts on page 11.
+    /// ```
+    /// let entry = "part_3.typ";
+    /// let root = "./booklet";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_page_number_offset(10)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_page_number_offset(mut self, offset: usize) -> Self {
+        self.page_number_offset = Some(offset);
+        self
+    }
+
+    /// ## Producer
+    /// Default value: `"typst-lib-wrapper/{version} (typst {typst_version})"`
+    ///
+    /// Attributes the compiled output to your build pipeline (template version, build id, ...).
+    /// - PDF: sets the `Producer` field of the document information and the matching
+    /// `pdf:Producer` XMP metadata property. Typst still sets itself as the `Creator`.
+    /// - SVG: adds `<!-- producer -->` comment before the root element.
+    ///
+    /// If the PDF can't be updated, it's returned unchanged and a warning is added
+    /// to `warnings`.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "invoice.typ";
+    /// let root = "./templates";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_producer("invoices 2.1.0 (build 421)")
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_producer(mut self, producer: impl ToString) -> Self {
+        self.producer = Some(producer.to_string());
+        self
+    }
+
+    /// ## Heading numbering
+    /// Default value: none (headings aren't numbered)
+    ///
+    /// Applies the numbering `pattern` to all headings, same as `#set heading(numbering: "...")`
+    /// at the start of the document. Handy for enforcing a house numbering style across many
+    /// templates without repeating it in each of them.
+    ///
+    /// The pattern is a **default style**, so `#set heading(numbering: ...)` in the document
+    /// (or a template) takes precedence, including `#set heading(numbering: none)`.
+    ///
+    /// Invalid pattern (without any counting symbol, for example `"--"` instead of `"1.a"`)
+    /// makes [build](Self::build) fail with [WrapperError::InvalidNumberingPattern].
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "contract.typ";
+    /// let root = "./legal";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_heading_numbering("1.1.a)")
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_heading_numbering(mut self, pattern: &str) -> Self {
+        self.heading_numbering = Some(pattern.to_string());
+        self
+    }
+
+    /// ## Locale
+    /// Default value: `"en"` (typst default, no region)
+    ///
+    /// Sets the default text language and region, same as `#set text(lang: "..", region: "..")`
+    /// at the start of the document. Accepts BCP 47 / POSIX style tags like `"de"`,
+    /// `"de-AT"`, `"pt_BR"`, `"zh-Hans-CN"` or `"de_AT.UTF-8"`.
+    ///
+    /// The locale is a **default style**, so `#set text(lang: ...)` in the document
+    /// (or a template) takes precedence.
+    ///
+    /// With the current typst internationalization support, the locale affects:
+    /// - hyphenation (when enabled, for example in justified paragraphs),
+    /// - smart quotes style,
+    /// - built-in terms (`"Figure"`, `"Table"`, `"Contents"`, `"Bibliography"`, ...),
+    /// - bibliography locale and language specific font shaping features.
+    ///
+    /// It does **not** affect number formatting or [datetime](
+    /// crate::reexports::Datetime) display, typst doesn't localize those.
+    ///
+    /// Languages typst doesn't have translations for fall back to English terms.
+    /// Script and unknown region subtags are ignored. Malformed language code (not 2 or 3
+    /// ASCII letters) makes [build](Self::build) fail with [WrapperError::InvalidLocale].
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "letter.typ";
+    /// let root = "./letters";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_locale("de-AT")
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_locale(mut self, locale: &str) -> Self {
+        self.locale = Some(locale.to_string());
+        self
+    }
+
+    /// ## Deny warnings
+    /// Default value: false
+    ///
+    /// Treats warnings as errors (for example in CI). If the compilation emits any warning,
+    /// `output` of every `compile_` method is `None` and the warnings are copied into
+    /// `errors`. Warnings still also appear in the `warnings` field for inspection.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiled = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_deny_warnings(true)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler")
+    ///     .compile_pdf();
+    ///
+    /// if compiled.output.is_none() {
+    ///     dbg!(compiled.errors); // Errors, including the warnings.
+    ///     std::process::exit(1);
+    /// }
+    /// ```
+    pub fn with_deny_warnings(mut self, deny_warnings: bool) -> Self {
+        self.deny_warnings = Some(deny_warnings);
+        self
+    }
+
+    /// Parses the `locale` tag into typst [Lang] and optional [Region].
+    ///
+    /// Encoding and modifier (`".UTF-8"`, `"@euro"`) are stripped, script subtags and
+    /// unknown subtags are skipped. Returns `None` if the language is malformed.
+    ///
+    /// ### Used internally.
+    fn parse_locale(locale: &str) -> Option<(Lang, Option<Region>)> {
+        let tag = locale.split(['.', '@']).next().unwrap_or_default();
+        let mut subtags = tag.split(['-', '_']);
+
+        let language = subtags.next().unwrap_or_default();
+        if !language.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let lang = Lang::from_str(language).ok()?;
+
+        // First two letter subtag is the region, e.g. `AT` in `de-AT` or `CN` in `zh-Hans-CN`.
+        let region = subtags
+            .find(|subtag| subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic()))
+            .and_then(|subtag| Region::from_str(subtag).ok());
+
+        return Some((lang, region));
+    }
+
+    /// Optional [ureq::Agent]
+    ///
+    /// Used for downloading packages from the repository. Primarily exists to enable loading
+    /// custom TLS configuration.
+    ///
+    /// # Example
+    /// How to create [ureq::Agent].
+    /// ```
+    /// let agent = ureq::AgentBuilder::new().build();
+    ///
+    /// let content = r##"
+    ///     #set page(paper: "a4");
+    ///     = Hello World
+    ///     Hello world from typst.
+    /// "##;
+    ///
+    /// let compiler = CompilerBuilder::with_content_input(content)
+    ///     .with_agent(agent)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_agent(mut self, agent: ureq::Agent) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    /// ## Network timeout
+    /// Default value: none (only the operating system timeouts apply)
+    ///
+    /// Timeout of the whole package download request (connecting, sending and reading),
+    /// so a slow or unreachable package repository doesn't block the compilation for long.
+    /// Timed out download fails the compilation with [PackageError::NetworkFailed](
+    /// crate::reexports::PackageError::NetworkFailed) ([WrapperError::PackageDownloadFailed]
+    /// outside of the compilation).
+    ///
+    /// # Note
+    /// Applies only to the default [ureq::Agent]. Agent provided with
+    /// [with_agent](Self::with_agent) keeps its own settings.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_network_timeout(std::time::Duration::from_secs(15))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_network_timeout(mut self, timeout: Duration) -> Self {
+        self.network_timeout = Some(timeout);
+        self
+    }
+
+    /// ## Download retries
+    /// Default value: no retries
+    ///
+    /// Retries a package download that failed with a transient error (connection error,
+    /// timeout, `429` or `5xx` response) up to `count` times. Waits `backoff` before the first
+    /// retry and doubles the wait after each retry (exponential backoff). Missing package
+    /// (`404`) is never retried. If every attempt fails, the compilation fails with
+    /// [PackageError::NetworkFailed](crate::reexports::PackageError::NetworkFailed)
+    /// ([WrapperError::PackageDownloadFailed] outside of the compilation).
+    ///
+    /// Retries respect the [request deadline](Self::with_request_deadline), no retry is started
+    /// after it.
+    ///
+    /// # Example
+    /// Retries up to 3 times, waiting 1, 2 and 4 seconds.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_download_retries(3, std::time::Duration::from_secs(1))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_download_retries(mut self, count: u32, backoff: Duration) -> Self {
+        self.download_retries = Some((count, backoff));
+        self
+    }
+
+    /// ## Maximum extracted package size
+    /// Default value: unlimited
+    ///
+    /// Limits the total size (in bytes) of files extracted from a package downloaded from
+    /// the repository. If the limit is exceeded, extraction is aborted, partially extracted
+    /// package is deleted and the compilation fails with a package error.
+    ///
+    /// Protects the disk from decompression bombs, useful when compiling third-party documents.
+    /// Packages that are already on the disk are not checked.
+    ///
+    /// # Example
+    /// Limits downloaded packages to 50 MB.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_max_extracted_size(50 * 1024 * 1024)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_max_extracted_size(mut self, bytes: u64) -> Self {
+        self.max_extracted_size = Some(bytes);
+        self
+    }
+
+    /// ## Request deadline
+    /// Default value: none (only the [ureq::Agent] timeouts apply)
+    ///
+    /// Point in time after which package downloads are aborted, even in the middle of
+    /// the transfer. Ties package fetching into a per-request timeout budget.
+    ///
+    /// Exceeded deadline fails the compilation with [PackageError::NetworkFailed](
+    /// crate::reexports::PackageError::NetworkFailed). Packages that are already
+    /// on the disk are not affected.
+    ///
+    /// # Example
+    /// Gives the whole request 10 seconds.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_request_deadline(deadline)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_request_deadline(mut self, deadline: Instant) -> Self {
+        self.request_deadline = Some(deadline);
+        self
+    }
+
+    /// ## Package registry
+    /// Default value: only `@preview` packages are downloaded, from the typst repository
+    ///
+    /// Downloads packages in the `namespace` from the registry `host` (for example an internal
+    /// mirror), using the same layout as the typst repository:
+    /// `{host}/{namespace}/{name}-{version}.tar.gz`. Downloaded packages are cached the same
+    /// way as `@preview` packages. Can be called multiple times, once for each namespace.
+    ///
+    /// Configuring the `"preview"` namespace overrides the typst repository. Packages in
+    /// namespaces without configured host are only looked up locally.
+    ///
+    /// # Example
+    /// Fetches `@internal/...` packages from a company mirror.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_package_registry("internal", "https://packages.example.com")
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_package_registry(mut self, namespace: impl ToString, host: impl ToString) -> Self {
+        self.package_registries.insert(namespace.to_string(), host.to_string());
+        self
+    }
+
+    /// ## Offline mode
+    /// Default value: false
+    ///
+    /// Packages are never downloaded, the network is never accessed. Packages that aren't
+    /// already on the disk fail the compilation immediately with [PackageError::NotFound](
+    /// crate::reexports::PackageError::NotFound), instead of a slow or confusing network error.
+    /// Useful in sandboxed environments.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_offline(true)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = Some(offline);
+        self
+    }
+
+    /// ## Package cache directory
+    /// Default value: OS default data and cache directories (`{dir}/typst/packages`)
+    ///
+    /// Packages are looked up in and downloaded to the `dir` (for example a writable volume
+    /// mounted into a container) instead of the OS default directories. Directory layout is
+    /// `{dir}/{namespace}/{name}/{version}`, same as typst `--package-cache-path`.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_package_cache_dir("/var/cache/typst-packages")
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_package_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.package_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// ## Download progress
+    /// Default value: none
+    ///
+    /// Registers a `callback` called while a package is being downloaded, after each read
+    /// chunk, with the number of bytes read so far and the total size (from `Content-Length`
+    /// header) if the server provided it. Useful for showing a spinner or a progress bar.
+    ///
+    /// # Note / Warning
+    /// The [Compiler] can be used across threads, so the `callback` must be `Send + Sync`.
+    /// It is called on the thread compiling the document, keep it short.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_download_progress(|read, total| match total {
+    ///         Some(total) => eprint!("\rDownloading package: {read}/{total} bytes"),
+    ///         None => eprint!("\rDownloading package: {read} bytes")
+    ///     })
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_download_progress(
+        mut self,
+        callback: impl Fn(u64, Option<u64>) + Send + Sync + 'static
+    ) -> Self {
+        self.download_progress = Some(DownloadProgress(std::sync::Arc::new(callback)));
+        self
+    }
+
+    /// ## Package checksums
+    /// Default value: none (downloaded packages aren't verified)
+    ///
+    /// Expected SHA-256 checksums (hex encoded) of package archives (`.tar.gz`), keyed by
+    /// the package specification. Downloaded archive of a pinned package is verified before
+    /// it's extracted. On mismatch nothing is extracted and the compilation fails with
+    /// a package error describing [WrapperError::PackageChecksumMismatch] (both checksums
+    /// included). Packages without a checksum and packages already on the disk aren't verified.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use std::str::FromStr;
+    /// use typst_lib_wrapper::reexports::PackageSpec;
+    ///
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let spec = PackageSpec::from_str("@preview/cetz:0.3.1").expect("Invalid spec");
+    /// let checksum = String::from("c5f3...e1b2"); // SHA-256 of `cetz-0.3.1.tar.gz`.
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_package_checksums(HashMap::from([(spec, checksum)]))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// ```
+    pub fn with_package_checksums(mut self, checksums: HashMap<PackageSpec, String>) -> Self {
+        self.package_checksums = checksums;
+        self
+    }
+
+    /// Resolves the system-global `path`, [WrapperError::InputNotFound] if it doesn't exist.
+    ///
+    /// ### Used internally.
+    fn canonicalize_input(path: &Path) -> WrapperResult<PathBuf> {
+        return path.canonicalize().map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => WrapperError::InputNotFound(path.to_path_buf()),
+            _ => WrapperError::from(err),
+        });
+    }
+
+    /// Removes `"."` and `".."` components from the `path`. Doesn't touch the file system,
+    /// so symbolic links aren't resolved.
+    ///
+    /// ### Used internally.
+    fn normalize_lexically(path: &Path) -> PathBuf {
+        let mut normalized = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {},
+                Component::ParentDir => { normalized.pop(); },
+                _ => normalized.push(component)
             }
+        }
+
+        return normalized;
+    }
+
+    /// Resolves the project root, the entry path and the virtual path of the entry
+    /// within the root (`None` for content input).
+    ///
+    /// ### Used internally.
+    fn resolve_paths(&self) -> WrapperResult<(PathBuf, PathBuf, Option<VirtualPath>)> {
+        return match &self.input {
+            Input::Content(_) => {
+                Ok((PathBuf::from("."), PathBuf::from(CONTENT_ENTRY), None))
+            },
+            Input::ContentWithRoot { root, .. } => {
+                Ok((Self::canonicalize_input(root)?, PathBuf::from(CONTENT_ENTRY), None))
+            },
             Input::File { entry, root } => {
-                // Appends `entry` filename to `root`
-                let mut entry_path = root.clone();
-                entry_path.push(entry);
-
-                // Resolve the system-global root directory.
-                let canon_root_path: PathBuf =
-                    root.canonicalize().map_err(|err| match err.kind() {
-                        std::io::ErrorKind::NotFound => WrapperError::InputNotFound(root),
-                        _ => WrapperError::from(err),
-                    })?;
-
-                // Resolve the system-global input path.
-                let canon_entry_path: PathBuf =
-                    entry_path.canonicalize().map_err(|err| match err.kind() {
-                        std::io::ErrorKind::NotFound => WrapperError::InputNotFound(entry_path),
-                        _ => WrapperError::from(err),
-                    })?;
+                // Resolve the system-global root directory and input path.
+                let canon_root_path: PathBuf = Self::canonicalize_input(root)?;
+                let canon_entry_path: PathBuf = Self::canonicalize_input(&root.join(entry))?;
 
                 // Resolve the virtual path of the main file within the project root.
-                let main_path =
-                    VirtualPath::within_root(&canon_entry_path, &canon_root_path).ok_or(
-                        WrapperError::InputOutsideRoot(canon_entry_path, canon_root_path.clone()),
-                    )?;
+                // Entry reached through a symlink within the root may resolve outside of it,
+                // so the entry path (lexically) within the canonical root is checked as well.
+                let main_path = VirtualPath::within_root(&canon_entry_path, &canon_root_path)
+                    .or_else(|| {
+                        let entry_path = Self::normalize_lexically(&canon_root_path.join(entry));
+                        VirtualPath::within_root(&entry_path, &canon_root_path)
+                    })
+                    .ok_or(WrapperError::InputOutsideRoot(
+                        canon_entry_path,
+                        canon_root_path.clone()
+                    ))?;
+
+                // Entry path within the root, not the symlink target.
+                let entry_path = canon_root_path.join(main_path.as_rootless_path());
+
+                Ok((canon_root_path, entry_path, Some(main_path)))
+            }
+        };
+    }
+
+    /// Resolves the input without building the [Compiler], useful for debugging
+    /// "file not found" issues. Returns the canonicalized `(root, entry)` paths.
+    ///
+    /// Content input has `"."` as the root (or the canonicalized root, see
+    /// [Input::content_with_root]) and `"<content>"` marker as the entry.
+    ///
+    /// Returns the same input errors as [build](Self::build), [WrapperError::InputNotFound]
+    /// or [WrapperError::InputOutsideRoot], without loading fonts or the library.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let builder = CompilerBuilder::with_file_input(entry, root);
+    /// match builder.resolve_input() {
+    ///     Ok((root, entry)) => println!("Compiling {} in {}", entry.display(), root.display()),
+    ///     Err(err) => eprintln!("{err}")
+    /// }
+    /// ```
+    pub fn resolve_input(&self) -> WrapperResult<(PathBuf, PathBuf)> {
+        let (root, entry, _) = self.resolve_paths()?;
+        return Ok((root, entry));
+    }
+
+    /// Creates an in-memory main source that updates the page counter to start from
+    /// `page_number_offset + 1` and then includes the `entry` (content `entry` by the
+    /// [WRAPPED_CONTENT_ENTRY] path).
+    ///
+    /// ### Used internally.
+    fn entry_wrapper(entry: &Source, is_content: bool, page_number_offset: usize) -> Source {
+        // In-memory (content) entry has a fake id, it's included by the reserved path
+        // (`\u{0}` escapes the NUL character).
+        // Typst paths always use forward slashes, regardless of the platform.
+        let entry_path: String = match is_content {
+            true => String::from("/\\u{0}<content>"),
+            false => entry.id().vpath().as_rootless_path()
+                .iter()
+                .map(|component| {
+                    component.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"")
+                })
+                .fold(String::new(), |path, component| path + "/" + &component)
+        };
+
+        let text = format!(
+            "#counter(page).update({})\n#include \"{entry_path}\"\n",
+            page_number_offset + 1
+        );
+
+        let vpath = VirtualPath::new("page_number_offset.typ");
+        return Source::new(FileId::new_fake(vpath), text);
+    }
+
+    /// Finalizes the configuration and takes ownership of the [CompilerBuilder].
+    /// Returns an error if something goes wrong.
+    ///
+    /// # Note / Warning
+    /// ### Blocking Mutex
+    /// It is recommended to add fonts using [FontCache] to avoid locking the font mutex
+    /// for too long. This mutex is **NOT ASYNC** so keep that in mind. Use **'blocking task'**
+    /// if you wish to compile documents in an async environment. Also, if compiling with an
+    /// opt-in feature (`"parallel_compilation"`) to PNGs or SVGs, the compiler tries to
+    /// encode/convert images to bytes in parallel with `rayon`. To sync up compiled pages,
+    /// again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// ### [FontCache] cloning
+    /// Before building the compiler, builder locks [FontCache] and **clones** it for itself.
+    /// To reduce [FontCache] size it is recommended to avoid loading all system fonts and
+    /// to load only needed fonts. In practise this won't be that big of a deal, because
+    /// all fonts are lazily loaded into memory, but they stay there, so **manually empty**
+    /// the [FontCache].
+    pub fn build(self) -> WrapperResult<Compiler> {
+        // Resolves the input paths, before loading the library and fonts.
+        let (root_path, _, main_path) = self.resolve_paths()?;
+
+        // Validates PDF standards upfront, so incompatible combinations fail early.
+        let pdf_standards: Vec<PdfStandard> = self.pdf_standards
+            .unwrap_or(vec![PdfStandard::V_1_7]);
+        PdfStandards::new(&pdf_standards)
+            .map_err(|err| WrapperError::InvalidPdfStandards(err.to_string()))?;
+
+        let packages = PackageSettings {
+            http_client: create_http_agent(self.agent, self.network_timeout),
+            max_extracted_size: self.max_extracted_size,
+            deadline: self.request_deadline,
+            registries: self.package_registries,
+            offline: self.offline.unwrap_or(false),
+            cache_dir: self.package_cache_dir,
+            progress: self.download_progress,
+            checksums: self.package_checksums,
+            retries: self.download_retries.map(|(count, _)| count).unwrap_or(0),
+            retry_backoff: self.download_retries.map(|(_, backoff)| backoff).unwrap_or_default()
+        };
+
+        let now = self.now.unwrap_or_else(chrono::Utc::now);
+        let ppi: f32 = self.ppi.unwrap_or(144.0); // default typst ppi: 144.0
+        let background = self.background.unwrap_or(Color::WHITE);
+        let mut files: HashMap<FileId, LazyFile> = HashMap::new();
+
+        // Virtual files are resolved from memory, before touching the disk.
+        for (path, bytes) in self.virtual_files.into_iter() {
+            let id = FileId::new(None, VirtualPath::new(path));
+            files.insert(id, LazyFile::new_virtual(id, bytes));
+        }
+
+        // Fingerprint of the library inputs, used to identify compilation inputs.
+        let library_fingerprint: u128 = typst_utils::hash128(
+            &(
+                &self.sys_inputs,
+                &self.custom_data,
+                &self.modules,
+                &self.library,
+                &self.heading_numbering,
+                &self.locale
+            )
+        );
+
+        // Convert the input pairs to a dictionary.
+        let sys_inputs: Dict = self
+            .sys_inputs
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into_value()))
+            .collect();
+        let mut library = match self.library {
+            Some(mut library) => {
+                // Provided library keeps its own `sys` module, unless inputs are set.
+                if !sys_inputs.is_empty() {
+                    library.global.scope_mut().define_module(sys::module(sys_inputs));
+                }
+                library
+            },
+            None => LibraryBuilder::default().with_inputs(sys_inputs).build()
+        };
+
+        // Rejects custom data keys that don't follow the recommended naming.
+        if self.strict_custom_data.unwrap_or(false) {
+            for (key, _) in self.custom_data.iter() {
+                if library.global.scope().get(key).is_some() {
+                    return Err(WrapperError::ShadowedStandardSymbol(key.clone()));
+                }
+                if !key.starts_with('_') {
+                    return Err(WrapperError::InvalidCustomDataKey(key.clone()));
+                }
+            }
+        }
+
+        // Provides a way to load modules and custom data into the library, by overriding `keys`.
+        // Keeps track of standard library symbols overwritten by the modules and custom data.
+        let modules = self.modules
+            .into_iter()
+            .map(|(name, module)| (name, Value::Module(module)));
+        let mut shadowed_symbols: Vec<String> = Vec::new();
+        let mut defined_keys: Vec<String> = Vec::new();
+        for (key, value) in modules.chain(self.custom_data) {
+            let is_defined = library.global.scope().get(&key).is_some();
+            if is_defined && !defined_keys.contains(&key) && !shadowed_symbols.contains(&key) {
+                shadowed_symbols.push(key.clone());
+            }
+            defined_keys.push(key.clone());
+
+            let key_eco = ecow::EcoString::from(key);
+            library
+                .global
+                .scope_mut()
+                .define_captured(key_eco, value, Capturer::Function, Span::detached());
+        }
+
+        // Sets default heading numbering, documents can still override it.
+        if let Some(pattern) = self.heading_numbering {
+            let numbering = NumberingPattern::from_str(&pattern)
+                .map_err(|_| WrapperError::InvalidNumberingPattern(pattern))?;
+            library.styles.set(HeadingElem::set_numbering(Some(Numbering::Pattern(numbering))));
+        }
+
+        // Sets default text language and region, documents can still override it.
+        if let Some(locale) = self.locale {
+            let (lang, region) = Self::parse_locale(&locale)
+                .ok_or(WrapperError::InvalidLocale(locale))?;
+            library.styles.set(TextElem::set_lang(lang));
+            library.styles.set(TextElem::set_region(region));
+        }
+
+        let entry: Source = match (self.input, main_path.clone()) {
+            (Input::Content(content) | Input::ContentWithRoot { content, .. }, _) => {
+                // Fake id never collides with ids of real files.
+                Source::new(FileId::new_fake(VirtualPath::new(CONTENT_ENTRY)), content)
+            }
+            (Input::File { .. }, main_path) => {
+                let main_path = main_path.expect("File input has a virtual path");
                 let main_file_id = FileId::new(None, main_path);
 
                 let entry_file: &mut LazyFile = files
                     .entry(main_file_id)
                     .or_insert_with(|| LazyFile::new(main_file_id));
 
-                let entry_source = entry_file
-                    .source(&canon_root_path, &http_client)
-                    .map_err(WrapperError::from)?;
-
-                root_path = canon_root_path;
-                entry_source
+                entry_file
+                    .source(&root_path, &[], &packages)
+                    .map_err(WrapperError::from)?
             }
         };
 
+        // Resolves the system-global additional roots.
+        let mut additional_roots: Vec<PathBuf> = Vec::with_capacity(self.additional_roots.len());
+        for root in self.additional_roots.iter() {
+            additional_roots.push(Self::canonicalize_input(root)?);
+        }
+
+        // Wraps the entry with a page counter update, if needed.
+        let entry_wrapper: Option<Source> = match self.page_number_offset {
+            Some(offset) if offset > 0 => {
+                Some(Self::entry_wrapper(&entry, main_path.is_none(), offset))
+            },
+            _ => None
+        };
+        // Id the wrapper includes the content input by, compared in `Compiler::source`.
+        let wrapped_entry_id: Option<FileId> = (entry_wrapper.is_some() && main_path.is_none())
+            .then(|| FileId::new(None, VirtualPath::new(WRAPPED_CONTENT_ENTRY)));
+
+        // Typst PDF exporter always subsets fonts, full embedding can't be honored.
+        let mut build_warnings: Vec<SourceDiagnostic> = Vec::new();
+        if self.full_font_embedding.unwrap_or(false) {
+            let message = "Full font embedding isn't supported by typst 0.12, fonts are subsetted";
+            build_warnings.push(SourceDiagnostic::warning(Span::detached(), message));
+        }
+
         // Skips adding fonts to the font cache if no custom paths provided.
         if !self.font_paths.is_empty() {
             FontCache::insert_many(self.font_paths)?;
@@ -706,19 +1887,126 @@ impl CompilerBuilder {
 
         Ok(Compiler {
             root: root_path,
+            additional_roots,
             entry,
+            entry_wrapper,
+            wrapped_entry_id,
             files: Mutex::new(files),
-            pdf_a: self.pdf_a.unwrap_or(false),
+            pdf_standards,
+            pdf_ident: self.pdf_ident,
+            pdf_timestamp: self.pdf_timestamp,
+            build_warnings,
+            producer: self.producer.unwrap_or(String::from(DEFAULT_PRODUCER)),
+            deny_warnings: self.deny_warnings.unwrap_or(false),
 
             library: LazyHash::new(library),
+            library_fingerprint,
+            shadowed_symbols,
             book: LazyHash::new(book),
             fonts,
 
-            http_client,
+            packages,
 
             ppi,
             background,
+            svg_background: self.svg_background.unwrap_or(Some(background)),
+            svg_responsive: self.svg_responsive.unwrap_or(false),
+            autocrop: self.autocrop.unwrap_or(false),
+            autocrop_padding: self.autocrop_padding.unwrap_or(0),
             now,
+            utc_dates: self.utc_dates.unwrap_or(false),
+            datetime_precision: self.datetime_precision.unwrap_or_default(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::tests::temp_dir;
+
+    #[test]
+    fn default_producer_follows_typst_dependency() {
+        let manifest: toml::Table = toml::from_str(include_str!("../Cargo.toml"))
+            .expect("Invalid Cargo.toml");
+        let version = manifest["dependencies"]["typst"]
+            .as_str()
+            .expect("Typst dependency isn't a plain version");
+
+        assert!(DEFAULT_PRODUCER.ends_with(&format!("(typst {version})")));
+    }
+
+    #[test]
+    fn missing_font_file_fails_the_build() {
+        let dir = temp_dir("missing-font");
+        let font_path = dir.join("missing.ttf");
+
+        let result = CompilerBuilder::with_content_input("Hello")
+            .add_font_path(&font_path)
+            .build();
+
+        assert!(matches!(
+            result,
+            Err(WrapperError::FontLoadingError(path, _)) if path == font_path
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn non_font_file_fails_the_build() {
+        let dir = temp_dir("non-font");
+        let font_path = dir.join("notes.ttf");
+        std::fs::write(&font_path, "Not a font").unwrap();
+
+        let result = CompilerBuilder::with_content_input("Hello")
+            .add_font_path(&font_path)
+            .build();
+
+        assert!(matches!(
+            result,
+            Err(WrapperError::FontFaceLoadingError(path)) if path == font_path
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_root_and_entry_directory_resolve_within_root() {
+        use std::os::unix::fs::symlink;
+
+        // `link` -> `project`, `project/chapters` -> `shared` (outside of the root).
+        let dir = temp_dir("symlinked-root");
+        std::fs::create_dir_all(dir.join("project")).unwrap();
+        std::fs::create_dir_all(dir.join("shared")).unwrap();
+        std::fs::write(dir.join("shared/main.typ"), "Hello").unwrap();
+        symlink(dir.join("shared"), dir.join("project/chapters")).unwrap();
+        symlink(dir.join("project"), dir.join("link")).unwrap();
+
+        let builder = CompilerBuilder::with_file_input("chapters/main.typ", dir.join("link"));
+        let canon_root = dir.join("project").canonicalize().unwrap();
+        assert_eq!(
+            builder.resolve_input().unwrap(),
+            (canon_root.clone(), canon_root.join("chapters/main.typ"))
+        );
+        assert!(builder.build().is_ok());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn binary_custom_data_is_embedded_as_image() {
+        let mut pixmap = tiny_skia::Pixmap::new(2, 2).unwrap();
+        pixmap.fill(tiny_skia::Color::from_rgba8(255, 128, 0, 255));
+        let png: Vec<u8> = pixmap.encode_png().unwrap();
+
+        let compiler = CompilerBuilder::with_content_input("#image.decode(_LOGO, width: 4cm)")
+            .add_custom_data_one(("_LOGO", typst::foundations::Bytes::from(png)))
+            .build()
+            .expect("Couldn't build the compiler");
+        let output = compiler.compile_pdf();
+
+        assert!(output.errors.is_empty(), "{:?}", output.errors);
+        let pdf = output.output.expect("Missing PDF");
+        assert!(pdf.windows(b"/Subtype /Image".len()).any(|x| x == b"/Subtype /Image"));
+    }
+}
@@ -1,23 +1,34 @@
 //! Provides a way to compile typst Document to PDF, PNG or SVG.
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::num::NonZeroUsize;
+use std::ops::{Bound, Range, RangeBounds, RangeInclusive};
+use std::path::{Path, PathBuf};
 
 use parking_lot::Mutex;
-use ecow::EcoVec;
+use ecow::{eco_format, EcoString, EcoVec};
 use typst::diag::{FileResult, SourceDiagnostic, Warned};
 use typst_pdf::{PdfOptions, PdfStandard, PdfStandards};
-use typst::foundations::{Bytes, Datetime, Smart};
+use typst::foundations::{Bytes, Datetime, NativeElement, Smart, Value};
+use typst::introspection::MetadataElem;
+use typst::layout::{Abs, Page, PageRanges, Size};
 use typst::model::Document;
 use typst::text::{Font, FontBook};
 use typst::{Library, World};
 use typst::visualize::{Color, Paint};
+use tiny_skia::Pixmap;
 use typst_utils::LazyHash;
-use typst_syntax::{FileId, Source, Span};
+use typst_syntax::package::PackageSpec;
+use typst_syntax::{FileId, Source, Span, VirtualPath};
 
 use crate::files::LazyFile;
 use crate::fonts::{LazyFont, FontCache};
-use crate::parameters::CompilerOutput;
+use crate::errors::{WrapperError, WrapperResult};
+use crate::package::{prepare_package, PackageSettings};
+use crate::parameters::{
+    CompiledBytes, CompilerOutput, DatetimePrecision, MultiOutput, OutputFormat, SvgPage
+};
+use crate::raster;
 
 /// [Compiler] instance build from [CompilerBuilder](crate::builder::CompilerBuilder).
 ///
@@ -49,19 +60,35 @@ use crate::parameters::CompilerOutput;
 #[derive(Debug)]
 pub struct Compiler {
     pub(crate) root: PathBuf,
+    pub(crate) additional_roots: Vec<PathBuf>,
     pub(crate) entry: Source,
+    pub(crate) entry_wrapper: Option<Source>,
+    pub(crate) wrapped_entry_id: Option<FileId>,
     pub(crate) files: Mutex<HashMap<FileId, LazyFile>>,
-    pub(crate) pdf_a: bool,
+    pub(crate) pdf_standards: Vec<PdfStandard>,
+    pub(crate) pdf_ident: Option<String>,
+    pub(crate) pdf_timestamp: Option<Option<chrono::DateTime<chrono::Utc>>>,
+    pub(crate) build_warnings: Vec<SourceDiagnostic>,
+    pub(crate) producer: String,
+    pub(crate) deny_warnings: bool,
 
     pub(crate) library: LazyHash<Library>,
+    pub(crate) library_fingerprint: u128,
+    pub(crate) shadowed_symbols: Vec<String>,
     pub(crate) book: LazyHash<FontBook>,
     pub(crate) fonts: Vec<LazyFont>,
 
-    pub(crate) http_client: ureq::Agent,
+    pub(crate) packages: PackageSettings,
 
     pub(crate) ppi: f32,
     pub(crate) background: Color,
+    pub(crate) svg_background: Option<Color>,
+    pub(crate) svg_responsive: bool,
+    pub(crate) autocrop: bool,
+    pub(crate) autocrop_padding: u32,
     pub(crate) now: chrono::DateTime<chrono::Utc>,
+    pub(crate) utc_dates: bool,
+    pub(crate) datetime_precision: DatetimePrecision,
 }
 
 /// A world that provides access to the operating system.
@@ -82,28 +109,40 @@ impl World for Compiler {
     }
 
     /// Access the main source file.
+    ///
+    /// If the entry is wrapped (see [with_page_number_offset](
+    /// crate::builder::CompilerBuilder::with_page_number_offset)), the wrapper is the main file.
     fn main(&self) -> FileId {
-        self.entry.id()
+        match &self.entry_wrapper {
+            Some(wrapper) => wrapper.id(),
+            None => self.entry.id()
+        }
     }
 
     /// Try to access the specified source file. If the [FileId] points to a "file" with in memory
     /// contents, the contents are retrieved immediately. This is the case for the
     /// [Input::Content](crate::Input::Content).
     fn source(&self, id: FileId) -> FileResult<Source> {
-        let in_memory_file = id
-            .vpath()
-            .as_rootless_path()
-            .to_str()
-            .map(|x| x.contains(crate::RESERVED_IN_MEMORY_IDENTIFIER))
-            .unwrap_or(false);
-        if in_memory_file { return Ok(self.entry.clone()); }
+        if let Some(wrapper) = self.entry_wrapper.as_ref().filter(|x| x.id() == id) {
+            return Ok(wrapper.clone());
+        }
+
+        // Entry is kept in memory, it can be replaced with `set_main_source`.
+        if id == self.entry.id() {
+            return Ok(self.entry.clone());
+        }
+
+        // In-memory (content) entry has a fake id, wrapper includes it by the reserved id.
+        if self.wrapped_entry_id == Some(id) {
+            return Ok(self.entry.clone());
+        }
 
-        self.slot(id, |slot| slot.source(&self.root, &self.http_client))
+        self.slot(id, |slot| slot.source(&self.root, &self.additional_roots, &self.packages))
     }
 
     /// Try to access the specified file.
     fn file(&self, id: FileId) -> FileResult<Bytes> {
-        self.slot(id, |slot| slot.file(&self.root, &self.http_client))
+        self.slot(id, |slot| slot.file(&self.root, &self.additional_roots, &self.packages))
     }
 
     /// Try to access the font with the given index in the font book.
@@ -113,13 +152,15 @@ impl World for Compiler {
 
     /// Get the current date.
     ///
-    /// If no offset is specified, the local date should be chosen. Otherwise, the UTC
-    /// date should be chosen with the corresponding offset in hours.
+    /// If no offset is specified, the local date should be chosen (UTC date if
+    /// [with_utc_dates](crate::builder::CompilerBuilder::with_utc_dates) is enabled).
+    /// Otherwise, the UTC date should be chosen with the corresponding offset in hours.
     ///
     /// If this function returns `None`, Typst's `datetime` function will return an error.
     fn today(&self, offset: Option<i64>) -> Option<Datetime> {
-        // The time with the specified UTC offset, or within the local time zone.
+        // The time with the specified UTC offset, or within the local time zone (or UTC).
         let with_offset = match offset {
+            None if self.utc_dates => self.now.fixed_offset(),
             None => self.now.with_timezone(&chrono::Local).fixed_offset(),
             Some(hours) => {
                 let seconds = i32::try_from(hours).ok()?.checked_mul(3600)?;
@@ -128,7 +169,10 @@ impl World for Compiler {
             }
         };
 
-        return Self::date_convert_ymd(with_offset);
+        return match self.datetime_precision {
+            DatetimePrecision::DateOnly => Self::date_convert_ymd(with_offset),
+            DatetimePrecision::DateTime => Self::date_convert_ymd_hms(with_offset)
+        };
     }
 }
 
@@ -142,6 +186,327 @@ impl Compiler {
         f(map.entry(id).or_insert_with(|| LazyFile::new(id)))
     }
 
+    /// Reads the raw bytes of the file with `id`, exactly as they are provided to typst.
+    ///
+    /// Public wrapper around [World::file]. Files are resolved the same way as during
+    /// compilation, so packages are downloaded if necessary. Useful for debugging what
+    /// `#image` or `#read` actually receive.
+    ///
+    /// # Example
+    /// ```
+    /// use typst_lib_wrapper::reexports::{FileId, VirtualPath};
+    ///
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// let id = FileId::new(None, VirtualPath::new("images/logo.png"));
+    /// let bytes = compiler.read_file(id).expect("Couldn't read file");
+    /// println!("{} bytes", bytes.len());
+    /// ```
+    pub fn read_file(&self, id: FileId) -> FileResult<Bytes> {
+        return self.file(id);
+    }
+
+    /// Reads the raw bytes of the file with `path` relative to the project root, exactly as
+    /// they are provided to typst.
+    ///
+    /// Same as [read_file](Self::read_file), but takes a path instead of the [FileId].
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// let bytes = compiler.read_root_file("data/values.csv").expect("Couldn't read file");
+    /// println!("{}", String::from_utf8_lossy(&bytes));
+    /// ```
+    pub fn read_root_file(&self, path: impl AsRef<Path>) -> FileResult<Bytes> {
+        let id = FileId::new(None, VirtualPath::new(path));
+        return self.file(id);
+    }
+
+    /// Returns `custom_data` keys (and [module](crate::builder::CompilerBuilder::with_module)
+    /// names) that overwrote an existing standard library symbol (for example `"text"` or
+    /// `"calc"`) when the [Compiler] was built, in definition order.
+    ///
+    /// Keys that defined new symbols aren't included. Serves as an audit trail of the standard
+    /// library behavior changed by the custom data.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .add_custom_data_one(("lorem", "Placeholder"))
+    ///     .add_custom_data_one(("_COMPANY", "ACME"))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// assert_eq!(compiler.shadowed_symbols(), ["lorem"]);
+    /// ```
+    pub fn shadowed_symbols(&self) -> &[String] {
+        return &self.shadowed_symbols;
+    }
+
+    /// Returns warnings about the configuration, raised once when the [Compiler] was built.
+    ///
+    /// They aren't repeated in `warnings` of the compilations and aren't affected by
+    /// [with_deny_warnings](crate::builder::CompilerBuilder::with_deny_warnings).
+    /// For example, requesting unsupported
+    /// [full font embedding](crate::builder::CompilerBuilder::with_full_font_embedding).
+    ///
+    /// # Example
+    /// ```
+    /// let compiler = CompilerBuilder::with_content_input("Hello")
+    ///     .with_full_font_embedding(true)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// assert_eq!(compiler.build_warnings().len(), 1);
+    /// ```
+    pub fn build_warnings(&self) -> &[SourceDiagnostic] {
+        return &self.build_warnings;
+    }
+
+    /// Returns the [Compiler] as a typst [World], so typst APIs the wrapper doesn't wrap
+    /// (yet) can be used directly, for example `typst::compile` or `typst_ide` functions.
+    ///
+    /// The [Compiler] implements [World] itself, so `&compiler` can be used as well.
+    /// Sources are resolved the same way as with the wrapper methods (content input,
+    /// page number offset, virtual files, additional roots and packages).
+    ///
+    /// # Note / Warning
+    /// Fonts lazily loaded this way aren't written back into the
+    /// [FontCache](crate::fonts::FontCache). Call [reset](Self::reset) between the runs
+    /// to pick up changed files.
+    ///
+    /// # Example
+    /// Compiles the document with typst directly.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// let warned = typst::compile(compiler.as_world());
+    /// if let Ok(document) = warned.output {
+    ///     println!("{} pages", document.pages.len());
+    /// }
+    /// ```
+    pub fn as_world(&self) -> &dyn World {
+        return self;
+    }
+
+    /// Returns files accessed since the last [reset](Self::reset), starting with the entry,
+    /// the rest sorted by package and path.
+    ///
+    /// ### Used internally.
+    pub(crate) fn accessed_files(&self) -> Vec<FileId> {
+        let mut files: Vec<FileId> = self.files
+            .lock()
+            .iter()
+            .filter(|(id, file)| file.accessed() && **id != self.entry.id())
+            .map(|(id, _)| *id)
+            .collect();
+        files.sort_by_key(|id| (id.package().map(PackageSpec::to_string), id.vpath().clone()));
+        files.insert(0, self.entry.id());
+
+        return files;
+    }
+
+    /// Returns packages the compiled document depends on (sorted), derived from the files
+    /// accessed during the compilation (since the last [reset](Self::reset)). Packages
+    /// imported by other packages are included.
+    ///
+    /// Doesn't compile anything, call it after a borrowing compilation (for example
+    /// [compile_pdf_ref](Self::compile_pdf_ref)). Useful for dependency auditing
+    /// or generating a lockfile.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// let compiled = compiler.compile_pdf_ref();
+    /// for package in compiler.used_packages() {
+    ///     println!("{package}");
+    /// }
+    /// ```
+    pub fn used_packages(&self) -> Vec<PackageSpec> {
+        let mut packages: Vec<PackageSpec> = self.accessed_files()
+            .iter()
+            .filter_map(|id| id.package().cloned())
+            .collect();
+        packages.sort_by_key(PackageSpec::to_string);
+        packages.dedup();
+
+        return packages;
+    }
+
+    /// Makes sure all packages `specs` are available on the disk, downloading them if necessary,
+    /// without compiling anything.
+    ///
+    /// Packages are resolved with the package configuration of `self` (registries, cache
+    /// directory, checksums, ...). Useful for doing all network IO up front, in a controlled
+    /// phase, and compiling offline later. Dependencies of the packages aren't prefetched,
+    /// see [dependencies](crate::package::dependencies).
+    ///
+    /// All packages are processed, failures don't stop the prefetching. Returns
+    /// [WrapperError::PackagePrefetchFailed] with each failed package and its error.
+    ///
+    /// # Example
+    /// ```
+    /// use std::str::FromStr;
+    /// use typst_lib_wrapper::reexports::PackageSpec;
+    ///
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// let specs = [
+    ///     PackageSpec::from_str("@preview/cetz:0.3.1").expect("Invalid spec"),
+    ///     PackageSpec::from_str("@preview/tablex:0.0.9").expect("Invalid spec")
+    /// ];
+    /// let prefetched = compiler.prefetch_packages(&specs);
+    /// if let Err(WrapperError::PackagePrefetchFailed(failed)) = prefetched {
+    ///     for (spec, err) in failed {
+    ///         eprintln!("{spec}: {err}");
+    ///     }
+    /// }
+    /// ```
+    pub fn prefetch_packages(&self, specs: &[PackageSpec]) -> WrapperResult<()> {
+        let failed: Vec<(PackageSpec, WrapperError)> = specs
+            .iter()
+            .filter_map(|spec| {
+                prepare_package(spec, &self.packages).err().map(|err| (spec.clone(), err))
+            })
+            .collect();
+
+        if !failed.is_empty() {
+            return Err(WrapperError::PackagePrefetchFailed(failed));
+        }
+
+        return Ok(());
+    }
+
+    /// Replaces the content of the main (entry) source, keeping its [FileId].
+    ///
+    /// Useful for interactive (REPL-like) tools, new content can be compiled again without
+    /// building a new [Compiler] (and cloning the font cache). Typst memoization
+    /// reuses unchanged parts of the previous compilation, so recompiling is fast.
+    ///
+    /// Entry keeps its path, so with file input, relative imports, files and packages are still
+    /// resolved from the project root as usual. The entry file on the disk is **not** changed
+    /// and it isn't read again by [reset](Self::reset), the replaced content is kept.
+    ///
+    /// Combine with the borrowing `_ref` methods (for example [compile_pdf_ref](
+    /// Self::compile_pdf_ref)) for fast edit-compile loops.
+    ///
+    /// # Example
+    /// ```
+    /// let mut compiler = CompilerBuilder::with_content_input("= Hello")
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// for content in ["= Hello", "= Hello World", "= Hello World!"] {
+    ///     compiler.set_main_source(content.to_string());
+    ///     let compiled = compiler.compile_pdf_ref();
+    ///     dbg!(compiled.errors);
+    /// }
+    /// ```
+    pub fn set_main_source(&mut self, content: String) {
+        self.entry.replace(&content);
+    }
+
+    /// Prepares `self` for the next compilation, so changed files are read from the disk again.
+    ///
+    /// Within one compilation, each file is read from the disk only once. Once accessed, the file
+    /// keeps its contents across compilations, until this method is called. Call it between
+    /// runs of the borrowing `_ref` methods (for example [compile_pdf_ref](Self::compile_pdf_ref))
+    /// when the project files may have changed.
+    ///
+    /// Processed files are kept, only files whose contents changed are processed again.
+    /// Together with typst memoization (`comemo`), unchanged parts of the document aren't
+    /// compiled again.
+    ///
+    /// # Note
+    /// Memoized results are kept in a global cache. Long-running processes should evict it from
+    /// time to time, for example with `comemo::evict(10)` after each compilation.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let mut compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// loop {
+    ///     let compiled = compiler.compile_pdf_ref();
+    ///     if let Some(pdf) = compiled.output {
+    ///         std::fs::write("./main.pdf", pdf).expect("Couldn't write PDF");
+    ///     }
+    ///
+    ///     wait_for_changes(); // Your file watcher.
+    ///     compiler.reset();
+    /// }
+    /// ```
+    pub fn reset(&mut self) {
+        self.files.get_mut().values_mut().for_each(LazyFile::reset);
+    }
+
+    /// Drops all loaded project and package files, so the next compilation reads (and
+    /// processes) every file from the disk again. Virtual files are kept (see
+    /// [add_virtual_file](crate::builder::CompilerBuilder::add_virtual_file)).
+    ///
+    /// Unlike [reset](Self::reset), processed files **and their fingerprints** are dropped.
+    /// [reset](Self::reset) keeps them: a file is read again, but if its contents (fingerprint)
+    /// didn't change, the previously processed source is reused. After this method there's
+    /// nothing to compare against, so every file is processed again. Use it to free memory
+    /// held by files that are no longer used, or if a file could change without changing its
+    /// fingerprint (which shouldn't happen). Typst memoization (`comemo`) still reuses results
+    /// for sources with unchanged contents.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let mut compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// let first = compiler.compile_pdf_ref();
+    ///
+    /// // Project was restructured, previously loaded files aren't needed anymore.
+    /// compiler.reset_file_cache();
+    /// let second = compiler.compile_pdf_ref();
+    /// ```
+    pub fn reset_file_cache(&mut self) {
+        let files = self.files.get_mut();
+        files.retain(|_, file| file.is_virtual());
+        files.values_mut().for_each(LazyFile::clear);
+    }
+
     /// Converts [chrono::Datelike] to [typst::foundations::Datetime].
     ///
     /// Ignores time, uses just date. If the conversion fails, returns `None`.
@@ -173,7 +538,9 @@ impl Compiler {
 
     /// Compiles and consumes `self` into a typst document.
     ///
-    /// Function returns a tuple with optional Document and [SourceDiagnostic] [EcoVec].
+    /// Useful for custom export pipelines (own raster backend, page post-processing, ...),
+    /// pages are laid out, but not encoded. The document type is re-exported as
+    /// [Document](crate::reexports::Document).
     ///
     /// Returns Document [CompilerOutput]. \
     /// If there's an error during compilation it will return `None` variant for `output`, also
@@ -190,13 +557,54 @@ impl Compiler {
     /// To sync up compiled pages, again it uses **SYNC** mutex. \
     /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
     ///
+    /// # Example
+    /// Skips the built-in encoders and inspects the laid out pages directly.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_document();
+    ///
+    /// if let Some(document) = compiled.output {
+    ///     for (index, page) in document.pages.iter().enumerate() {
+    ///         let size = page.frame.size();
+    ///         println!("Page {index}: {}pt x {}pt", size.x.to_pt(), size.y.to_pt());
+    ///     }
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_document(self) -> CompilerOutput<Document> {
+        return self.compile_document_ref();
+    }
+
+    /// Compiles `self` into a typst document **without** consuming it.
+    ///
+    /// Returns Document [CompilerOutput]. The [Compiler] can be used again afterwards,
+    /// for example to export a modified document with [export_pdf_from](
+    /// Self::export_pdf_from). Lazily loaded fonts are **cloned** into the
+    /// [FontCache](crate::fonts::FontCache).
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    /// Please use **'blocking task'** provided by your async runtime.
+    pub fn compile_document_ref(&self) -> CompilerOutput<Document> {
+        return self.deny_warnings(self.compile_document_inner());
+    }
+
+    /// Compiles `self` into a typst document, warnings are **not** denied.
+    ///
     /// ### Used internally.
-    fn compile_document(self) -> CompilerOutput<Document> {
-        let Warned { output, warnings } = typst::compile(&self);
+    pub(crate) fn compile_document_inner(&self) -> CompilerOutput<Document> {
+        let Warned { output, warnings } = typst::compile(self);
         let compilation_result = output;
 
         // Tries to update the font cache, ignores errors.
-        let _ = FontCache::update_cache(self.fonts);
+        let _ = FontCache::update_cache(self.fonts.clone());
 
         return match compilation_result {
             Ok(doc) => CompilerOutput {
@@ -212,6 +620,20 @@ impl Compiler {
         };
     }
 
+    /// If warnings are denied (see [with_deny_warnings](
+    /// crate::builder::CompilerBuilder::with_deny_warnings)) and there are any, drops
+    /// the `output` and copies the warnings into errors.
+    ///
+    /// ### Used internally.
+    fn deny_warnings<T>(&self, mut compiler_output: CompilerOutput<T>) -> CompilerOutput<T> {
+        if self.deny_warnings && !compiler_output.warnings.is_empty() {
+            compiler_output.output = None;
+            compiler_output.errors.extend(compiler_output.warnings.iter().cloned());
+        }
+
+        return compiler_output;
+    }
+
     /// Compiles typst Document into PDF bytes and consumes `self`.
     ///
     /// Returns [Vec\<u8\>](Vec) [CompilerOutput].
@@ -241,116 +663,440 @@ impl Compiler {
     /// }
     /// ```
     pub fn compile_pdf(self) -> CompilerOutput<Vec<u8>> {
-        let timestamp = Self::date_convert_ymd_hms(self.now);
-        let pdf_a: bool = self.pdf_a;
-
-        let compiler_output: CompilerOutput<Document> = self.compile_document();
-        let mut errors = compiler_output.errors;
-        let warnings = compiler_output.warnings;
-
-        let document: Document = match compiler_output.output {
-            Some(doc) => doc,
-            None => return CompilerOutput {
-                output: None,
-                errors,
-                warnings
-            }
-        };
-
-        // IMPORTANT NOTE: PdfStandards::new(...) should never panic, but we will handle it just in case.
-        // https://github.com/typst/typst/blob/7add9b459a3ca54fca085e71f3dd4e611941c4cc/crates/typst-pdf/src/lib.rs#L114
-        let pdf_standards = if pdf_a {
-            match PdfStandards::new(&[PdfStandard::A_2b]) {
-                Ok(pdf_stndr) => pdf_stndr,
-                Err(err) => {
-                    errors.push(SourceDiagnostic::error(Span::detached(), err));
-                    return CompilerOutput {
-                        output: None,
-                        errors,
-                        warnings
-                    }
-                }
-            }
-        } else {
-            match PdfStandards::new(&[PdfStandard::V_1_7]) {
-                Ok(pdf_stndr) => pdf_stndr,
-                Err(err) => {
-                    errors.push(SourceDiagnostic::error(Span::detached(), err));
-                    return CompilerOutput {
-                        output: None,
-                        errors,
-                        warnings
-                    }
-                }
-            }
-        };
-
-        let pdf_options = PdfOptions {
-            ident: Smart::Auto,
-            timestamp,
-            standards: pdf_standards,
-            page_ranges: None // `None` exports all pages.
-        };
-
-        let mut pdf_bytes: Option<Vec<u8>> = None;
-
-        match typst_pdf::pdf(&document, &pdf_options) {
-            Ok(bytes) => { pdf_bytes = Some(bytes); },
-            Err(err_vec) => { errors.extend(err_vec); }
-        };
-
-        return CompilerOutput {
-            output: pdf_bytes,
-            errors,
-            warnings
-        };
+        return self.compile_pdf_ref();
     }
 
-    /// Compiles typst Document into a collection of PNG bytes and consumes `self`.
+    /// Compiles typst Document into PDF bytes, consumes `self` and returns also the packages
+    /// the document depends on (see [used_packages](Self::used_packages)).
     ///
-    /// One item for each page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
+    /// Returns [Vec\<u8\>](Vec) [CompilerOutput] and [Vec\<PackageSpec\>](Vec).
     ///
     /// # Note / Warning
     /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
     /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
-    ///
-    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
-    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
-    /// To sync up compiled pages, again it uses **SYNC** mutex. \
-    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    /// Please use **'blocking task'** provided by your async runtime.
     ///
     /// # Example
-    /// Compiles Document to multiple PNGs and saves them all.
+    /// Writes a simple lockfile next to the PDF.
     /// ```
     /// let entry = "main.typ";
     /// let root = "./project";
     ///
-    /// // Build the compiler and compile to PNG.
     /// let compiler = CompilerBuilder::with_file_input(entry, root)
     ///     .build()
     ///     .expect("Couldn't build the compiler");
-    /// let compiled = compiler.compile_png();
+    /// let (compiled, packages) = compiler.compile_pdf_with_deps();
     ///
-    /// if let Some(pages) = compiled.output {
-    ///     // Writes images one by one.
-    ///     pages.iter().enumerate().for_each(|(index, page)| {
-    ///         let filename = format!("./output/{index}.png");
-    ///         std::fs::write(filename, page)
-    ///             .expect("Couldn't write PNG");
-    ///     });
-    /// } else {
-    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// if let Some(pdf) = compiled.output {
+    ///     std::fs::write("./main.pdf", pdf).expect("Couldn't write PDF");
+    ///
+    ///     let lockfile: Vec<String> = packages.iter().map(|x| x.to_string()).collect();
+    ///     std::fs::write("./packages.lock", lockfile.join("\n"))
+    ///         .expect("Couldn't write lockfile");
     /// }
     /// ```
-    pub fn compile_png(self) -> CompilerOutput<Vec<Vec<u8>>> {
-        let ppi = self.ppi / 72.0;
-        let background = self.background;
-        let page_background = Smart::Custom(Some(Paint::Solid(background)));
+    pub fn compile_pdf_with_deps(self) -> (CompilerOutput<Vec<u8>>, Vec<PackageSpec>) {
+        let compiler_output: CompilerOutput<Vec<u8>> = self.compile_pdf_ref();
+        return (compiler_output, self.used_packages());
+    }
 
-        let compiler_output: CompilerOutput<Document> = self.compile_document();
-        let errors = compiler_output.errors;
+    /// Compiles typst Document into PDF bytes, consumes `self` and returns also the values
+    /// of all `metadata` elements in the document, in document order.
+    ///
+    /// Typst 0.12 doesn't expose a tracer, `metadata` is the way documents emit computed
+    /// data (table of contents, word count, totals, ...), e.g. `#metadata((words: 1234))`.
+    /// The document is compiled only once, unlike [compile_pdf](Self::compile_pdf) followed
+    /// by [query](Self::query). Values are empty if the compilation failed.
+    ///
+    /// Returns [Vec\<u8\>](Vec) [CompilerOutput] and [Vec\<Value\>](Vec).
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    /// Please use **'blocking task'** provided by your async runtime.
+    ///
+    /// # Example
+    /// Writes the PDF and the emitted metadata as JSON.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let (compiled, metadata) = compiler.compile_pdf_traced();
+    ///
+    /// if let Some(pdf) = compiled.output {
+    ///     std::fs::write("./main.pdf", pdf).expect("Couldn't write PDF");
+    ///
+    ///     let json = serde_json::to_string(&metadata).expect("Couldn't serialize");
+    ///     std::fs::write("./main.json", json).expect("Couldn't write metadata");
+    /// }
+    /// ```
+    pub fn compile_pdf_traced(self) -> (CompilerOutput<Vec<u8>>, Vec<Value>) {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let mut errors = compiler_output.errors;
+        let mut warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return (CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }, Vec::new())
+        };
+
+        let values: Vec<Value> = document.introspector
+            .query(&MetadataElem::elem().select())
+            .into_iter()
+            .filter_map(|element| element.to_packed::<MetadataElem>().map(|x| x.value.clone()))
+            .collect();
+
+        let exported: CompilerOutput<Vec<u8>> = self.export_pdf_from(&document);
+        errors.extend(exported.errors);
+        warnings.extend(exported.warnings);
+
+        // Export warnings are denied too.
+        let compiler_output = self.deny_warnings(CompilerOutput {
+            output: exported.output,
+            errors,
+            warnings
+        });
+        return (compiler_output, values);
+    }
+
+    /// Compiles typst Document into PDF bytes **without** consuming `self`.
+    ///
+    /// Same as [compile_pdf](Self::compile_pdf), but the [Compiler] can be used again,
+    /// recompiling is incremental. Call [reset](Self::reset) between the runs to pick up
+    /// changed files.
+    ///
+    /// Returns [Vec\<u8\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    /// Please use **'blocking task'** provided by your async runtime.
+    pub fn compile_pdf_ref(&self) -> CompilerOutput<Vec<u8>> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let mut errors = compiler_output.errors;
+        let mut warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None,
+                errors,
+                warnings
+            }
+        };
+
+        let exported: CompilerOutput<Vec<u8>> = self.export_pdf_from(&document);
+        errors.extend(exported.errors);
+        warnings.extend(exported.warnings);
+
+        // Export warnings are denied too.
+        return self.deny_warnings(CompilerOutput {
+            output: exported.output,
+            errors,
+            warnings
+        });
+    }
+
+    /// Exports an already compiled (and possibly modified) typst `document` into PDF bytes.
+    ///
+    /// Uses the PDF configuration of `self` (standards, identifier, producer, timestamp),
+    /// the sources of `self` are **not** compiled again. Combined with [compile_document_ref](
+    /// Self::compile_document_ref), this allows compiling once, modifying the pages
+    /// (injecting, removing, reordering, ...) and exporting the result.
+    ///
+    /// Returns [Vec\<u8\>](Vec) [CompilerOutput] with export errors and warnings only.
+    ///
+    /// # Example
+    /// Exports the document in reverse page order.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// if let Some(mut document) = compiler.compile_document_ref().output {
+    ///     document.pages.reverse();
+    ///
+    ///     let exported = compiler.export_pdf_from(&document);
+    ///     if let Some(pdf) = exported.output {
+    ///         std::fs::write("./reversed.pdf", pdf)
+    ///             .expect("Couldn't write PDF");
+    ///     }
+    /// }
+    /// ```
+    pub fn export_pdf_from(&self, document: &Document) -> CompilerOutput<Vec<u8>> {
+        return self.export_pdf_inner(document, None);
+    }
+
+    /// Exports an already compiled typst `document` into PDF bytes, only pages within
+    /// `page_ranges` (**1-based**, typst convention) are exported, all pages if `None`.
+    ///
+    /// ### Used internally.
+    fn export_pdf_inner(
+        &self,
+        document: &Document,
+        page_ranges: Option<PageRanges>
+    ) -> CompilerOutput<Vec<u8>> {
+        // Configured timestamp (or none at all), compiler creation time by default.
+        let timestamp = match self.pdf_timestamp {
+            Some(timestamp) => timestamp.and_then(Self::date_convert_ymd_hms),
+            None => Self::date_convert_ymd_hms(self.now)
+        };
+        let mut errors: EcoVec<SourceDiagnostic> = EcoVec::new();
+        let mut warnings: EcoVec<SourceDiagnostic> = EcoVec::new();
+
+        // IMPORTANT NOTE: Standards are validated when building the compiler, so
+        // PdfStandards::new(...) should never fail here, but we will handle it just in case.
+        // https://github.com/typst/typst/blob/7add9b459a3ca54fca085e71f3dd4e611941c4cc/crates/typst-pdf/src/lib.rs#L114
+        let pdf_standards = match PdfStandards::new(&self.pdf_standards) {
+            Ok(pdf_stndr) => pdf_stndr,
+            Err(err) => {
+                errors.push(SourceDiagnostic::error(Span::detached(), err));
+                return CompilerOutput {
+                    output: None,
+                    errors,
+                    warnings
+                }
+            }
+        };
+
+        let pdf_options = PdfOptions {
+            ident: match &self.pdf_ident {
+                Some(ident) => Smart::Custom(ident.as_str()),
+                None => Smart::Auto
+            },
+            timestamp,
+            standards: pdf_standards,
+            page_ranges // `None` exports all pages.
+        };
+
+        let mut pdf_bytes: Option<Vec<u8>> = None;
+
+        match typst_pdf::pdf(document, &pdf_options) {
+            Ok(bytes) => { pdf_bytes = Some(bytes); },
+            Err(err_vec) => { errors.extend(err_vec); }
+        };
+
+        // Sets the producer, keeps the original PDF if that fails.
+        if let Some(bytes) = pdf_bytes.as_mut() {
+            match crate::pdf::set_producer(bytes, &self.producer) {
+                Ok(updated) => { *bytes = updated; },
+                Err(err) => {
+                    let message = eco_format!("Couldn't set PDF producer: {err}");
+                    warnings.push(SourceDiagnostic::warning(Span::detached(), message));
+                }
+            }
+        }
+
+        return CompilerOutput {
+            output: pdf_bytes,
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles typst Document into PDF bytes, appends externally supplied PDFs
+    /// after the typst pages and consumes `self`. Requires `pdf_merge` feature.
+    ///
+    /// - `appendices`: PDF files (bytes) appended in order.
+    ///
+    /// Page tree of every appendix is attached after the typst pages. Typst bookmarks
+    /// are kept and a top-level bookmark `"Appendix N"` is added for the first page of each
+    /// appendix. Bookmarks inside the appendices are not merged.
+    ///
+    /// Returns [Vec\<u8\>](Vec) [CompilerOutput]. If any appendix can't be parsed, `output`
+    /// is `None` and the error is pushed to `errors`.
+    ///
+    /// # Note / Warning
+    /// Merged PDF is no longer PDF/A compliant, even if [with_pdf_a](
+    /// crate::builder::CompilerBuilder::with_pdf_a) is enabled.
+    ///
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    /// Please use **'blocking task'** provided by your async runtime.
+    ///
+    /// # Example
+    /// Appends terms and conditions to the generated report.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let terms = std::fs::read("./terms.pdf").expect("Couldn't read PDF");
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_pdf_with_appendices(vec![terms]);
+    ///
+    /// if let Some(pdf) = compiled.output {
+    ///     std::fs::write("./main.pdf", pdf)
+    ///         .expect("Couldn't write PDF"); // Writes PDF file.
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    #[cfg(feature = "pdf_merge")]
+    pub fn compile_pdf_with_appendices(self, appendices: Vec<Vec<u8>>) -> CompilerOutput<Vec<u8>> {
+        let compiler_output = self.compile_pdf();
+        let mut errors = compiler_output.errors;
         let warnings = compiler_output.warnings;
 
+        let pdf: Vec<u8> = match compiler_output.output {
+            Some(pdf) => pdf,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let output: Option<Vec<u8>> = match crate::pdf::append_pdfs(&pdf, &appendices) {
+            Ok(merged) => Some(merged),
+            Err(err) => {
+                errors.push(SourceDiagnostic::error(Span::detached(), err));
+                None
+            }
+        };
+
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles typst Document into PDF bytes with only the pages within `ranges`
+    /// and consumes `self`.
+    ///
+    /// - `ranges`: Inclusive ranges of page **indices, starting from 0** (same as
+    /// [compile_png_range](Self::compile_png_range)), `0..=0` is the first page.
+    ///
+    /// Pages are exported in document order, overlapping ranges don't duplicate pages.
+    /// Out-of-bounds indices and reversed ranges (`2..=0`) are ignored, if no page is within
+    /// the `ranges` the PDF has no pages. Uses typst native page ranges, so links and outline
+    /// entries pointing to the exported pages are kept.
+    ///
+    /// Returns [Vec\<u8\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    /// Please use **'blocking task'** provided by your async runtime.
+    ///
+    /// # Example
+    /// Exports the first page and pages 10 to 12 (indices 9 to 11).
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_pdf_pages(vec![0..=0, 9..=11]);
+    ///
+    /// if let Some(pdf) = compiled.output {
+    ///     std::fs::write("./excerpt.pdf", pdf)
+    ///         .expect("Couldn't write PDF");
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_pdf_pages(self, ranges: Vec<RangeInclusive<usize>>) -> CompilerOutput<Vec<u8>> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let mut errors = compiler_output.errors;
+        let mut warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        // Typst page ranges are 1-based.
+        let page_ranges = PageRanges::new(
+            ranges
+                .into_iter()
+                .map(|range| {
+                    let start = range.start().saturating_add(1);
+                    let end = range.end().saturating_add(1);
+                    NonZeroUsize::new(start)..=NonZeroUsize::new(end)
+                })
+                .collect()
+        );
+
+        let exported: CompilerOutput<Vec<u8>> =
+            self.export_pdf_inner(&document, Some(page_ranges));
+        errors.extend(exported.errors);
+        warnings.extend(exported.warnings);
+
+        // Export warnings are denied too.
+        return self.deny_warnings(CompilerOutput {
+            output: exported.output,
+            errors,
+            warnings
+        });
+    }
+
+    /// Compiles typst Document into multiple PDFs, each with (up to) `pages_per_chunk`
+    /// consecutive pages, and consumes `self`.
+    ///
+    /// The document is compiled once, chunks are exported separately. With an opt-in feature
+    /// (`"parallel_compilation"`) chunks are exported in parallel, which speeds up the export
+    /// of very large documents. Chunks can be merged afterwards.
+    ///
+    /// One item for each chunk, in page order. Returns [Vec\<Vec\<u8\>\>](Vec)
+    /// [CompilerOutput]. If `pages_per_chunk` is zero or any chunk fails to export, `output`
+    /// is `None` and the errors are added to `errors`.
+    ///
+    /// # Note / Warning
+    /// Every chunk is a standalone PDF. Links and outline (bookmark) entries pointing to pages
+    /// in other chunks **don't work**, each chunk also embeds its own copy of the fonts.
+    ///
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`), chunks are exported
+    /// in parallel with `rayon`. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Exports a large document in chunks of 100 pages.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_pdf_chunked(100);
+    ///
+    /// if let Some(chunks) = compiled.output {
+    ///     chunks.iter().enumerate().for_each(|(index, chunk)| {
+    ///         let filename = format!("./output/part-{index}.pdf");
+    ///         std::fs::write(filename, chunk)
+    ///             .expect("Couldn't write PDF");
+    ///     });
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_pdf_chunked(self, pages_per_chunk: usize) -> CompilerOutput<Vec<Vec<u8>>> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let mut errors = compiler_output.errors;
+        let mut warnings = compiler_output.warnings;
+
         let document: Document = match compiler_output.output {
             Some(doc) => doc,
             None => return CompilerOutput {
@@ -360,29 +1106,95 @@ impl Compiler {
             }
         };
 
-        let final_pages: Vec<Vec<u8>>;
+        if pages_per_chunk == 0 {
+            let message = "PDF chunk needs at least one page";
+            errors.push(SourceDiagnostic::error(Span::detached(), message));
+            return CompilerOutput { output: None, errors, warnings };
+        }
+
+        // Page ranges of the chunks, 1-based as expected by typst.
+        let chunks: Vec<PageRanges> = (0..document.pages.len())
+            .step_by(pages_per_chunk)
+            .map(|start| {
+                let end = (start + pages_per_chunk).min(document.pages.len());
+                PageRanges::new(vec![NonZeroUsize::new(start + 1)..=NonZeroUsize::new(end)])
+            })
+            .collect();
+
+        let export_chunk = |page_ranges: PageRanges| -> CompilerOutput<Vec<u8>> {
+            return self.export_pdf_inner(&document, Some(page_ranges));
+        };
+
+        // Sync export of chunks.
+        #[cfg(not(feature = "parallel_compilation"))]
+        let exported: Vec<CompilerOutput<Vec<u8>>> = chunks.into_iter().map(export_chunk).collect();
+
+        // Parallel export of chunks.
+        #[cfg(feature = "parallel_compilation")]
+        let exported: Vec<CompilerOutput<Vec<u8>>> = {
+            use rayon::iter::{IntoParallelIterator, ParallelIterator};
+            chunks.into_par_iter().map(export_chunk).collect()
+        };
+
+        let mut output: Option<Vec<Vec<u8>>> = Some(Vec::with_capacity(exported.len()));
+        for chunk in exported.into_iter() {
+            errors.extend(chunk.errors);
+            warnings.extend(chunk.warnings);
+            match (output.as_mut(), chunk.output) {
+                (Some(pdfs), Some(pdf)) => pdfs.push(pdf),
+                _ => output = None // Any failed chunk fails the whole export.
+            }
+        }
+
+        // Export warnings are denied too.
+        return self.deny_warnings(CompilerOutput {
+            output,
+            errors,
+            warnings
+        });
+    }
+
+    /// Encodes every page from `pages` with `encode` function. One item for each page.
+    ///
+    /// Returns a tuple with optional encoded pages and [SourceDiagnostic] [EcoVec].
+    /// Encoding errors are appended to `errors`. If any page encoutered an error
+    /// all pages are discarded and `None` is returned.
+    ///
+    /// # Note / Warning
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) pages are encoded
+    /// in parallel with `rayon`. To sync up encoded pages, it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// ### Used internally.
+    pub(crate) fn encode_pages<T, F>(
+        pages: Vec<Page>,
+        errors: EcoVec<SourceDiagnostic>,
+        encode: F
+    ) -> (Option<Vec<T>>, EcoVec<SourceDiagnostic>)
+    where
+        T: Send,
+        F: Fn(usize, Page) -> Result<T, EcoString> + Sync
+    {
+        let final_pages: Vec<Option<T>>;
         let final_errors: EcoVec<SourceDiagnostic>;
 
-        // Sync compilation of pages.
+        // Sync encoding of pages.
         #[cfg(not(feature = "parallel_compilation"))]
         {
             // Gets number of pages in a document and allocates memory upfront.
-            let pages_count = document.pages.len();
-            let mut pages_buffer: Vec<Vec<u8>> = vec![Vec::new(); pages_count];
+            let pages_count = pages.len();
+            let mut pages_buffer: Vec<Option<T>> = Vec::with_capacity(pages_count);
             let mut pages_errors = errors;
 
-            for (page_index, mut page) in document.pages.into_iter().enumerate() {
-                page.fill = page_background.clone();
-
-                match typst_render::render(&page, ppi).encode_png() {
-                    Ok(buf) => { // Write encoded PNG to the buffer.
-                        pages_buffer[page_index] = buf;
+            for (page_index, page) in pages.into_iter().enumerate() {
+                match encode(page_index, page) {
+                    Ok(buf) => { // Write encoded page to the buffer.
+                        pages_buffer.push(Some(buf));
                     },
                     Err(err) => { // Write error to the errors list.
-                        let encoding_error = SourceDiagnostic::error(
-                            Span::detached(), err.to_string()
-                        );
+                        let encoding_error = SourceDiagnostic::error(Span::detached(), err);
                         pages_errors.push(encoding_error);
+                        pages_buffer.push(None);
                     }
                 }
             }
@@ -391,67 +1203,1216 @@ impl Compiler {
             final_errors = pages_errors;
         }
 
-        // Parallel compilation of pages.
+        // Parallel encoding of pages.
         #[cfg(feature = "parallel_compilation")]
         {
             use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
 
             // Gets number of pages in a document and allocates memory upfront.
-            // Because of parallel PNG encoding, the pages buffer needs to be inside a mutex.
+            // Because of parallel encoding, the pages buffer needs to be inside a mutex.
             // The same applies to errors.
-            let pages_count = document.pages.len();
-            let shared_pages_buffer: Mutex<Vec<Vec<u8>>> = Mutex::new(
-                vec![Vec::new(); pages_count]
+            let pages_count = pages.len();
+            let shared_pages_buffer: Mutex<Vec<Option<T>>> = Mutex::new(
+                (0..pages_count).map(|_| None).collect()
             );
             let shared_errors: Mutex<EcoVec<SourceDiagnostic>> = Mutex::new(errors);
 
-            let _ = document
-                .pages
-                .into_par_iter() // Tries to encode pages to PNG in parallel.
+            let _ = pages
+                .into_par_iter() // Tries to encode pages in parallel.
                 .enumerate()
-                .map(|(page_index, mut page)| {
-                    page.fill = page_background.clone();
-
-                    // Tries to encode page frame.
-                    match typst_render::render(&page, ppi).encode_png() {
-                        Ok(buf) => { // Write encoded PNG to the shared buffer.
+                .map(|(page_index, page)| {
+                    match encode(page_index, page) {
+                        Ok(buf) => { // Write encoded page to the shared buffer.
                             {
-                                shared_pages_buffer.lock()[page_index] = buf;
+                                shared_pages_buffer.lock()[page_index] = Some(buf);
                             }
                         },
                         Err(err) => { // Write error to the shared errors list.
-                            let encoding_error = SourceDiagnostic::error(
-                                Span::detached(), err.to_string()
-                            );
+                            let encoding_error = SourceDiagnostic::error(Span::detached(), err);
+
+                            {
+                                shared_errors.lock().push(encoding_error);
+                            }
+                        }
+                    };
+            }).collect::<Vec<()>>();
+
+            // Takes pages and errors from the mutex
+            final_pages = shared_pages_buffer.into_inner();
+            final_errors = shared_errors.into_inner();
+        }
+
+        // Checks if any page is missing, which indicates encoding error occured.
+        // Discards all pages if any encoutered an error.
+        let output: Option<Vec<T>> = final_pages.into_iter().collect();
+
+        return (output, final_errors);
+    }
+
+    /// Compiles typst Document into a collection of PNG bytes and consumes `self`.
+    ///
+    /// One item for each page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Compiles Document to multiple PNGs and saves them all.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// // Build the compiler and compile to PNG.
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_png();
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     // Writes images one by one.
+    ///     pages.iter().enumerate().for_each(|(index, page)| {
+    ///         let filename = format!("./output/{index}.png");
+    ///         std::fs::write(filename, page)
+    ///             .expect("Couldn't write PNG");
+    ///     });
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_png(self) -> CompilerOutput<Vec<Vec<u8>>> {
+        return self.compile_png_inner(.., Vec::new(), Vec::new());
+    }
+
+    /// Compiles typst Document into a collection of PNG bytes **without** consuming `self`.
+    ///
+    /// Same as [compile_png](Self::compile_png), but the [Compiler] can be used again,
+    /// recompiling is incremental. Call [reset](Self::reset) between the runs to pick up
+    /// changed files.
+    ///
+    /// One item for each page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    pub fn compile_png_ref(&self) -> CompilerOutput<Vec<Vec<u8>>> {
+        return self.compile_png_inner(.., Vec::new(), Vec::new());
+    }
+
+    /// Compiles typst Document into PNG pages one at a time and consumes `self`.
+    ///
+    /// Each page is rendered, encoded and handed to the callback `f` together with its index,
+    /// before the next page is rendered. Encoded pages aren't collected, so the caller can
+    /// write (or stream) each page and drop it, peak memory stays at roughly one page.
+    ///
+    /// If the callback returns an error (or a page couldn't be encoded), the remaining pages
+    /// are skipped and the error is reported in `errors`. Returns the number of pages as
+    /// [CompilerOutput] if all pages were handled.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// Pages are always rendered sequentially, even with an opt-in feature
+    /// (`"parallel_compilation"`).
+    ///
+    /// # Example
+    /// Writes pages of a large document to the disk as they're rendered.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_png_each(|index, page| {
+    ///     std::fs::write(format!("./output/{index}.png"), page)?;
+    ///     Ok(())
+    /// });
+    ///
+    /// match compiled.output {
+    ///     Some(pages_count) => println!("Written {pages_count} pages"),
+    ///     None => { dbg!(compiled.errors); } // Compilation or writing failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_png_each<F>(self, mut f: F) -> CompilerOutput<usize>
+    where
+        F: FnMut(usize, Vec<u8>) -> WrapperResult<()>
+    {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let mut errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let pages_count = document.pages.len();
+        for (index, page) in document.pages.into_iter().enumerate() {
+            let result: Result<(), EcoString> = Self::encode_png_page(
+                page,
+                self.ppi,
+                self.background,
+                self.autocrop,
+                self.autocrop_padding
+            ).and_then(|bytes| {
+                f(index, bytes).map_err(|err| eco_format!("Page {index} callback failed: {err}"))
+            });
+
+            if let Err(message) = result {
+                errors.push(SourceDiagnostic::error(Span::detached(), message));
+                return CompilerOutput {
+                    output: None,
+                    errors,
+                    warnings
+                };
+            }
+        }
+
+        return CompilerOutput {
+            output: Some(pages_count),
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles typst Document into a collection of PNG bytes with `ppi` pixels per inch
+    /// and consumes `self`.
+    ///
+    /// Overrides configured PPI (see [with_ppi](crate::builder::CompilerBuilder::with_ppi))
+    /// for this compilation only, so the same configuration can be used for thumbnails and
+    /// print resolution without rebuilding the compiler.
+    ///
+    /// One item for each page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
+    /// If `ppi` isn't a positive finite number, `output` is `None` and an error is added
+    /// to `errors` (the document isn't compiled).
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Compiles Document to low resolution thumbnails.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_png_at(36.0);
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     // Writes images one by one.
+    ///     pages.iter().enumerate().for_each(|(index, page)| {
+    ///         let filename = format!("./thumbnails/{index}.png");
+    ///         std::fs::write(filename, page)
+    ///             .expect("Couldn't write PNG");
+    ///     });
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_png_at(mut self, ppi: f32) -> CompilerOutput<Vec<Vec<u8>>> {
+        if !(ppi.is_finite() && ppi > 0.0) {
+            let message = eco_format!("PPI must be a positive number, got {ppi}");
+            return CompilerOutput {
+                output: None,
+                errors: EcoVec::from([SourceDiagnostic::error(Span::detached(), message)]),
+                warnings: EcoVec::new()
+            };
+        }
+
+        self.ppi = ppi;
+        return self.compile_png_inner(.., Vec::new(), Vec::new());
+    }
+
+    /// Compiles typst Document into a collection of PNG bytes, rendering each page with
+    /// its own pixels per inch, and consumes `self`.
+    ///
+    /// - `ppis`: Pixels per inch for each page, `ppis[0]` is used for the first page,
+    /// `ppis[1]` for the second and so on. Pages without specified PPI are rendered with
+    /// configured PPI (see [with_ppi](crate::builder::CompilerBuilder::with_ppi)).
+    ///
+    /// One item for each page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Renders the first (text) page at 150 PPI, the second (figure) page at 400 PPI
+    /// and all the other pages with configured PPI.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_png_per_page_ppi(vec![150.0, 400.0]);
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     // Writes images one by one.
+    ///     pages.iter().enumerate().for_each(|(index, page)| {
+    ///         let filename = format!("./output/{index}.png");
+    ///         std::fs::write(filename, page)
+    ///             .expect("Couldn't write PNG");
+    ///     });
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_png_per_page_ppi(self, ppis: Vec<f32>) -> CompilerOutput<Vec<Vec<u8>>> {
+        let invalid_ppis: EcoVec<SourceDiagnostic> = ppis
+            .iter()
+            .enumerate()
+            .filter(|(_, ppi)| !(ppi.is_finite() && **ppi > 0.0))
+            .map(|(index, ppi)| {
+                let message = eco_format!("PPI of page {index} must be positive, got {ppi}");
+                SourceDiagnostic::error(Span::detached(), message)
+            })
+            .collect();
+        if !invalid_ppis.is_empty() {
+            return CompilerOutput {
+                output: None,
+                errors: invalid_ppis,
+                warnings: EcoVec::new()
+            };
+        }
+
+        return self.compile_png_inner(.., ppis, Vec::new());
+    }
+
+    /// Compiles typst Document into a collection of PNG bytes, overriding the background
+    /// of each page, and consumes `self`.
+    ///
+    /// - `backgrounds`: Background [Color] for each page, `backgrounds[0]` is used for the first
+    /// page, `backgrounds[1]` for the second and so on. Pages without specified (or `None`)
+    /// background are rendered with configured background
+    /// (see [with_background](crate::builder::CompilerBuilder::with_background)).
+    ///
+    /// One item for each page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Renders the cover page with orange background and all the other pages with
+    /// configured background.
+    /// ```
+    /// use typst_lib_wrapper::reexports::Color;
+    ///
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let cover = Color::from_u8(247, 75, 0, 255);
+    /// let compiled = compiler.compile_png_backgrounds(vec![Some(cover)]);
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     // Writes images one by one.
+    ///     pages.iter().enumerate().for_each(|(index, page)| {
+    ///         let filename = format!("./output/{index}.png");
+    ///         std::fs::write(filename, page)
+    ///             .expect("Couldn't write PNG");
+    ///     });
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_png_backgrounds(
+        self,
+        backgrounds: Vec<Option<Color>>
+    ) -> CompilerOutput<Vec<Vec<u8>>> {
+        return self.compile_png_inner(.., Vec::new(), backgrounds);
+    }
+
+    /// Compiles typst Document into a collection of PNG bytes with pixel dimensions
+    /// and consumes `self`.
+    ///
+    /// One item for each page, a tuple of PNG bytes, width and height in pixels.
+    /// Dimensions are taken from the rendered image (after autocrop, if enabled), so there's
+    /// no need to parse the PNG header. Configured PPI, background and autocrop are applied.
+    ///
+    /// Returns [Vec\<(Vec\<u8\>, u32, u32)\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Prints the pixel dimensions of each page.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_png_with_sizes();
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     for (index, (_png, width, height)) in pages.iter().enumerate() {
+    ///         println!("Page {index}: {width}px x {height}px");
+    ///     }
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_png_with_sizes(self) -> CompilerOutput<Vec<(Vec<u8>, u32, u32)>> {
+        let ppi = self.ppi;
+        let background = self.background;
+        let autocrop = self.autocrop;
+        let autocrop_padding = self.autocrop_padding;
+
+        let compiler_output: CompilerOutput<Document> = self.compile_document();
+        let errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let (output, errors) = Self::encode_pages(document.pages, errors, |_, page| {
+            let pixmap =
+                Self::render_page_pixmap(page, ppi, background, autocrop, autocrop_padding)?;
+            let (width, height) = (pixmap.width(), pixmap.height());
+            let png = pixmap.encode_png().map_err(|err| EcoString::from(err.to_string()))?;
+            Ok((png, width, height))
+        });
+
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles typst Document into a single PNG contact sheet, a grid of all page thumbnails,
+    /// and consumes `self`.
+    ///
+    /// - `cols`: Number of grid columns, grid never has more columns than pages.
+    /// - `thumb_px`: Size of a grid cell in pixels, every page is scaled to fit into
+    /// a `thumb_px` x `thumb_px` square and centered in it.
+    /// - `gap_px`: Space between the cells in pixels.
+    ///
+    /// Configured background fills the pages and the gaps, configured PPI and autocrop
+    /// are ignored.
+    ///
+    /// Returns [Vec\<u8\>](Vec) [CompilerOutput]. If the document has no pages,
+    /// `cols` or `thumb_px` is zero, a thumbnail or the sheet is too large, `output` is `None`
+    /// and an error is added to `errors`.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Creates an overview of the document, 4 thumbnails per row.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_background(Color::from_u8(200, 200, 200, 255))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_contact_sheet(4, 256, 16);
+    ///
+    /// if let Some(sheet) = compiled.output {
+    ///     std::fs::write("./overview.png", sheet)
+    ///         .expect("Couldn't write PNG");
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_contact_sheet(
+        self,
+        cols: usize,
+        thumb_px: u32,
+        gap_px: u32
+    ) -> CompilerOutput<Vec<u8>> {
+        let background = self.background;
+
+        let compiler_output: CompilerOutput<Document> = self.compile_document();
+        let mut errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        if cols == 0 || thumb_px == 0 {
+            let message = "Contact sheet needs at least one column and non-zero thumbnail size";
+            errors.push(SourceDiagnostic::error(Span::detached(), message));
+            return CompilerOutput { output: None, errors, warnings };
+        }
+
+        // Thumbnail has to be allocatable, before it becomes a (huge) PPI for every page.
+        let thumb_size = Size::splat(Abs::pt(thumb_px as f64));
+        if let Err(err) = raster::checked_pixel_size(thumb_size, 72.0) {
+            let message = eco_format!("Thumbnail size {thumb_px} px is too large: {err}");
+            errors.push(SourceDiagnostic::error(Span::detached(), message));
+            return CompilerOutput { output: None, errors, warnings };
+        }
+
+        let (thumbnails, mut errors) = Self::encode_pages(document.pages, errors, |_, page| {
+            // Scales the larger page dimension to the cell size.
+            let size: Size = page.frame.size();
+            let longer_side = size.x.to_pt().max(size.y.to_pt());
+            if longer_side <= 0.0 {
+                return Err(EcoString::from("Can't create a thumbnail of an empty page"));
+            }
+            let ppi = (thumb_px as f64 / longer_side * 72.0) as f32;
+
+            Self::render_page_pixmap(page, ppi, background, false, 0)
+        });
+
+        let Some(thumbnails) = thumbnails else {
+            return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            };
+        };
+
+        if thumbnails.is_empty() {
+            errors.push(SourceDiagnostic::error(Span::detached(), "Document has no pages"));
+            return CompilerOutput { output: None, errors, warnings };
+        }
+
+        let sheet = raster::compose_grid(&thumbnails, cols, thumb_px, thumb_px, gap_px, background);
+        let output = match sheet.map(|sheet| sheet.encode_png()) {
+            Some(Ok(png)) => Some(png),
+            Some(Err(err)) => {
+                errors.push(SourceDiagnostic::error(Span::detached(), err.to_string()));
+                None
+            },
+            None => {
+                let message = "Contact sheet is too large";
+                errors.push(SourceDiagnostic::error(Span::detached(), message));
+                None
+            }
+        };
+
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles typst Document into a single PNG with all pages composed into a grid
+    /// and consumes `self`.
+    ///
+    /// - `columns`: Number of grid columns, grid never has more columns than pages.
+    /// Use `1` to stack the pages vertically.
+    /// - `gutter`: Space between the pages in pixels.
+    ///
+    /// Unlike [compile_contact_sheet](Self::compile_contact_sheet), pages are **not** scaled,
+    /// they're rendered with configured PPI, background and autocrop. Every grid cell is as
+    /// large as the largest page, smaller pages are centered in it. Configured background
+    /// fills the gutters.
+    ///
+    /// Returns [Vec\<u8\>](Vec) [CompilerOutput]. If the document has no pages, `output` is
+    /// `None` without an error. If `columns` is zero or the grid is too large, `output` is
+    /// `None` and an error is added to `errors`.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Creates a vertical thumbnail strip of the document.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_ppi(24.0)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_png_contact_sheet(1, 8);
+    ///
+    /// if let Some(strip) = compiled.output {
+    ///     std::fs::write("./strip.png", strip)
+    ///         .expect("Couldn't write PNG");
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_png_contact_sheet(self, columns: usize, gutter: u32) -> CompilerOutput<Vec<u8>> {
+        let ppi = self.ppi;
+        let background = self.background;
+        let autocrop = self.autocrop;
+        let autocrop_padding = self.autocrop_padding;
+
+        let compiler_output: CompilerOutput<Document> = self.compile_document();
+        let mut errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        if columns == 0 {
+            let message = "Contact sheet needs at least one column";
+            errors.push(SourceDiagnostic::error(Span::detached(), message));
+            return CompilerOutput { output: None, errors, warnings };
+        }
+
+        let (pixmaps, mut errors) = Self::encode_pages(document.pages, errors, |_, page| {
+            Self::render_page_pixmap(page, ppi, background, autocrop, autocrop_padding)
+        });
+
+        // Empty document isn't an error, there's just nothing to compose.
+        let pixmaps: Vec<Pixmap> = match pixmaps {
+            Some(pixmaps) if !pixmaps.is_empty() => pixmaps,
+            _ => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let cell_width = pixmaps.iter().map(Pixmap::width).max().unwrap_or_default();
+        let cell_height = pixmaps.iter().map(Pixmap::height).max().unwrap_or_default();
+
+        let sheet = raster::compose_grid(
+            &pixmaps,
+            columns,
+            cell_width,
+            cell_height,
+            gutter,
+            background
+        );
+        let output = match sheet.map(|sheet| sheet.encode_png()) {
+            Some(Ok(png)) => Some(png),
+            Some(Err(err)) => {
+                errors.push(SourceDiagnostic::error(Span::detached(), err.to_string()));
+                None
+            },
+            None => {
+                let message = "Contact sheet is too large";
+                errors.push(SourceDiagnostic::error(Span::detached(), message));
+                None
+            }
+        };
+
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles typst Document into a collection of PNG bytes, rendering only pages with
+    /// indices (**starting from 0**) within the `range`, and consumes `self`.
+    ///
+    /// Range is clamped to the document pages, so out-of-bounds ranges don't panic.
+    /// If no page is within the range, `output` is `Some` empty vector.
+    ///
+    /// One item for each rendered page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Renders a preview of the first three pages.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_png_range(0..3);
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     // Writes images one by one.
+    ///     pages.iter().enumerate().for_each(|(index, page)| {
+    ///         let filename = format!("./preview/{index}.png");
+    ///         std::fs::write(filename, page)
+    ///             .expect("Couldn't write PNG");
+    ///     });
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_png_range(self, range: impl RangeBounds<usize>) -> CompilerOutput<Vec<Vec<u8>>> {
+        return self.compile_png_inner(range, Vec::new(), Vec::new());
+    }
+
+    /// Compiles typst Document into a collection of PNG bytes where **1 point maps to exactly
+    /// 1 pixel** (PPI is always 72, configured PPI is ignored) and consumes `self`.
+    ///
+    /// Useful for compositing typst output over point-based coordinates, there's no DPI
+    /// conversion to reason about. Autocrop is **not applied**, so every image covers
+    /// the whole page and its top-left pixel is the top-left corner of the page.
+    /// Configured background is applied.
+    ///
+    /// One item for each page, a tuple of PNG bytes and page [Size] in points.
+    /// Pixel dimensions of the image are page dimensions rounded to the nearest integer
+    /// (at least 1 pixel), so they can differ from the [Size] by up to half a pixel.
+    /// Returns [Vec\<(Vec\<u8\>, Size)\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Prints the point size of each page.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_png_points();
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     for (index, (_png, size)) in pages.iter().enumerate() {
+    ///         println!("Page {index}: {}pt x {}pt", size.x.to_pt(), size.y.to_pt());
+    ///     }
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_png_points(self) -> CompilerOutput<Vec<(Vec<u8>, Size)>> {
+        let background = self.background;
+
+        let compiler_output: CompilerOutput<Document> = self.compile_document();
+        let errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let (output, errors) = Self::encode_pages(document.pages, errors, |_, page| {
+            let size: Size = page.frame.size();
+            Self::encode_png_page(page, 72.0, background, false, 0).map(|png| (png, size))
+        });
+
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
+
+    /// Clamps the `range` to `0..len`. Returns an empty range if the `range` is empty
+    /// or outside of `0..len`.
+    ///
+    /// ### Used internally.
+    pub(crate) fn clamp_range(range: impl RangeBounds<usize>, len: usize) -> Range<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len
+        };
+
+        let end = end.min(len);
+        return start.min(end)..end;
+    }
+
+    /// Compiles typst Document into a collection of PNG bytes.
+    ///
+    /// - `range`: Indices of pages to render, clamped to the document pages.
+    /// - `ppis`: Pixels per inch for each page, falls back to configured PPI.
+    /// - `backgrounds`: Background [Color] for each page, falls back to configured background.
+    ///
+    /// `ppis` and `backgrounds` are indexed by the page index in the document.
+    ///
+    /// ### Used internally.
+    fn compile_png_inner(
+        &self,
+        range: impl RangeBounds<usize>,
+        ppis: Vec<f32>,
+        backgrounds: Vec<Option<Color>>
+    ) -> CompilerOutput<Vec<Vec<u8>>> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let range = Self::clamp_range(range, document.pages.len());
+        let first_page = range.start;
+        let pages: Vec<Page> = document.pages
+            .into_iter()
+            .skip(range.start)
+            .take(range.len())
+            .collect();
+
+        let (output, errors) =
+            self.export_png_pages(pages, first_page, &ppis, &backgrounds, errors);
+
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
+
+    /// Encodes already compiled `pages` into a collection of PNG bytes.
+    ///
+    /// - `first_page`: Index of the first of `pages` in the document.
+    /// - `ppis`: Pixels per inch for each page, falls back to configured PPI.
+    /// - `backgrounds`: Background [Color] for each page, falls back to configured background.
+    ///
+    /// `ppis` and `backgrounds` are indexed by the page index in the document.
+    /// Encoding errors are appended to `errors`.
+    ///
+    /// ### Used internally.
+    fn export_png_pages(
+        &self,
+        pages: Vec<Page>,
+        first_page: usize,
+        ppis: &[f32],
+        backgrounds: &[Option<Color>],
+        errors: EcoVec<SourceDiagnostic>
+    ) -> (Option<Vec<Vec<u8>>>, EcoVec<SourceDiagnostic>) {
+        let default_ppi = self.ppi;
+        let default_background = self.background;
+        let autocrop = self.autocrop;
+        let autocrop_padding = self.autocrop_padding;
+
+        return Self::encode_pages(pages, errors, |index, page| {
+            let page_index = first_page + index;
+            let background = backgrounds
+                .get(page_index)
+                .copied()
+                .flatten()
+                .unwrap_or(default_background);
+            let ppi = ppis.get(page_index).copied().unwrap_or(default_ppi);
+
+            Self::encode_png_page(page, ppi, background, autocrop, autocrop_padding)
+        });
+    }
+
+    /// Renders a single `page` with `ppi` and `background` into a [Pixmap].
+    /// If `autocrop` is set, the background margins are trimmed (leaving `autocrop_padding`).
+    ///
+    /// Returns an error if the `ppi` is invalid or the page is too large to be rendered.
+    ///
+    /// ### Used internally.
+    fn render_page_pixmap(
+        mut page: Page,
+        ppi: f32,
+        background: Color,
+        autocrop: bool,
+        autocrop_padding: u32
+    ) -> Result<Pixmap, EcoString> {
+        raster::checked_pixel_size(page.frame.size(), ppi).map_err(EcoString::from)?;
+        page.fill = Smart::Custom(Some(Paint::Solid(background)));
+
+        // Pixels per point.
+        let mut pixmap = typst_render::render(&page, ppi / 72.0);
+        if autocrop {
+            pixmap = raster::autocrop(pixmap, background, autocrop_padding);
+        }
+
+        return Ok(pixmap);
+    }
+
+    /// Renders a single `page` with `ppi` and `background` and encodes it as PNG.
+    /// If `autocrop` is set, the background margins are trimmed (leaving `autocrop_padding`).
+    ///
+    /// ### Used internally.
+    fn encode_png_page(
+        page: Page,
+        ppi: f32,
+        background: Color,
+        autocrop: bool,
+        autocrop_padding: u32
+    ) -> Result<Vec<u8>, EcoString> {
+        let pixmap = Self::render_page_pixmap(page, ppi, background, autocrop, autocrop_padding)?;
+        return pixmap.encode_png().map_err(|err| err.to_string().into());
+    }
+
+    /// Compiles typst Document into a collection of JPEG bytes and consumes `self`.
+    /// Requires `jpeg_export` feature.
+    ///
+    /// - `quality`: JPEG quality, clamped to `1..=100`.
+    ///
+    /// Pages are rendered the same way as with [compile_png](Self::compile_png) (configured
+    /// PPI, background and autocrop), JPEG output is usually much smaller for photo-heavy
+    /// documents. JPEG has no transparency, transparent background is rendered white.
+    ///
+    /// One item for each page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Compiles Document to JPEG previews and saves them all.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_jpeg(80);
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     // Writes images one by one.
+    ///     pages.iter().enumerate().for_each(|(index, page)| {
+    ///         let filename = format!("./output/{index}.jpg");
+    ///         std::fs::write(filename, page)
+    ///             .expect("Couldn't write JPEG");
+    ///     });
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    #[cfg(feature = "jpeg_export")]
+    pub fn compile_jpeg(self, quality: u8) -> CompilerOutput<Vec<Vec<u8>>> {
+        let quality = quality.clamp(1, 100);
+        let ppi = self.ppi;
+        let background = self.background;
+        let autocrop = self.autocrop;
+        let autocrop_padding = self.autocrop_padding;
+
+        let compiler_output: CompilerOutput<Document> = self.compile_document();
+        let errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let (output, errors) = Self::encode_pages(document.pages, errors, |_, page| {
+            let pixmap =
+                Self::render_page_pixmap(page, ppi, background, autocrop, autocrop_padding)?;
+            raster::encode_jpeg(&pixmap, quality).map_err(EcoString::from)
+        });
+
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles typst Document and encodes **only the first page** into PNG bytes.
+    /// Consumes `self`.
+    ///
+    /// The whole document is still laid out, but only the first page is rendered and
+    /// encoded, which makes this the cheapest way to create a thumbnail/preview.
+    /// Configured PPI, background and autocrop are applied.
+    ///
+    /// Returns [Vec\<u8\>](Vec) [CompilerOutput]. If the document has no pages,
+    /// `output` is `None` and an error is added to `errors`.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    /// Please use **'blocking task'** provided by your async runtime.
+    ///
+    /// # Example
+    /// Creates a thumbnail of the document.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_ppi(36.0)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_first_page_png();
+    ///
+    /// if let Some(thumbnail) = compiled.output {
+    ///     std::fs::write("./thumbnail.png", thumbnail)
+    ///         .expect("Couldn't write PNG");
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_first_page_png(self) -> CompilerOutput<Vec<u8>> {
+        let ppi = self.ppi;
+        let background = self.background;
+        let autocrop = self.autocrop;
+        let autocrop_padding = self.autocrop_padding;
+
+        let compiler_output: CompilerOutput<Document> = self.compile_document();
+        let mut errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let first_page: Option<Page> = compiler_output
+            .output
+            .and_then(|document| document.pages.into_iter().next());
+
+        let output = match first_page {
+            Some(page) => {
+                match Self::encode_png_page(page, ppi, background, autocrop, autocrop_padding) {
+                    Ok(buf) => Some(buf),
+                    Err(err) => {
+                        errors.push(SourceDiagnostic::error(Span::detached(), err));
+                        None
+                    }
+                }
+            },
+            None => {
+                // 'Bubbles up' `None` variant, unless the document has no pages.
+                if errors.is_empty() {
+                    errors.push(SourceDiagnostic::error(Span::detached(), "Document has no pages"));
+                }
+                None
+            }
+        };
+
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles typst Document and renders page with `page_index` (**starting from 0**) into
+    /// the caller-provided `pixmap`. Doesn't consume `self`.
+    ///
+    /// Meant for hot paths that render many identically-sized pages, the same `pixmap`
+    /// buffer can be reused for every page. Configured PPI and background are applied,
+    /// autocrop is **ignored** (it changes the size).
+    ///
+    /// `pixmap` size must match the rendered page size (page size in points multiplied by
+    /// `ppi / 72`, rounded to the nearest integer), otherwise an error is returned and
+    /// the `pixmap` is left untouched. The size is checked before the page is rendered.
+    ///
+    /// Returns [()](unit) [CompilerOutput], `output` is `Some` if the page was rendered.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// Typst memoizes compilation, so calling this for every page of an unchanged document
+    /// compiles it only once. `typst_render` 0.12 can't draw into an existing canvas,
+    /// so the page is still rendered into a temporary buffer and then copied into
+    /// the `pixmap`.
+    ///
+    /// # Example
+    /// Renders every page of A4 document into the same buffer.
+    /// ```
+    /// use typst_lib_wrapper::reexports::Pixmap;
+    ///
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .with_ppi(72.0)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// // A4 page at 72 PPI.
+    /// let mut pixmap = Pixmap::new(595, 842).expect("Invalid size");
+    /// for page_index in 0..10 {
+    ///     let rendered = compiler.render_page_into(page_index, &mut pixmap);
+    ///     if rendered.output.is_none() {
+    ///         break;
+    ///     }
+    ///     // Uses `pixmap`...
+    /// }
+    /// ```
+    pub fn render_page_into(&self, page_index: usize, pixmap: &mut Pixmap) -> CompilerOutput<()> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let mut errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
 
-                            {
-                                shared_errors.lock().push(encoding_error);
-                            }
-                        }
-                    };
-            }).collect::<Vec<()>>();
+        let pages_count = document.pages.len();
+        let Some(page) = document.pages.into_iter().nth(page_index) else {
+            let message = eco_format!(
+                "Page index {page_index} out of range, document has {pages_count} pages"
+            );
+            errors.push(SourceDiagnostic::error(Span::detached(), message));
+            return CompilerOutput { output: None, errors, warnings };
+        };
 
-            // Takes pages and errors from the mutex
-            final_pages = shared_pages_buffer.into_inner();
-            final_errors = shared_errors.into_inner();
+        // Checks the size before rendering, so mismatched pages aren't rendered at all.
+        let size = match raster::checked_pixel_size(page.frame.size(), self.ppi) {
+            Ok(size) => size,
+            Err(err) => {
+                errors.push(SourceDiagnostic::error(Span::detached(), err));
+                return CompilerOutput { output: None, errors, warnings };
+            }
+        };
+        if size != (pixmap.width(), pixmap.height()) {
+            let message = eco_format!(
+                "Pixmap size {}x{} doesn't match rendered page size {}x{}",
+                pixmap.width(), pixmap.height(), size.0, size.1
+            );
+            errors.push(SourceDiagnostic::error(Span::detached(), message));
+            return CompilerOutput { output: None, errors, warnings };
         }
 
-        // Checks if any `page vector` is empty, which indicates
-        // encoding error occured. Discards all pages if any encoutered an error.
-        let encoding_error_occured = final_pages.iter().any(|x| x.is_empty());
-        let output: Option<Vec<Vec<u8>>> = if encoding_error_occured {
-            None
-        } else {
-            Some(final_pages)
+        match Self::render_page_pixmap(page, self.ppi, self.background, false, 0) {
+            Ok(rendered) => pixmap.data_mut().copy_from_slice(rendered.data()),
+            Err(err) => {
+                errors.push(SourceDiagnostic::error(Span::detached(), err));
+                return CompilerOutput { output: None, errors, warnings };
+            }
         };
 
         return CompilerOutput {
-            output,
-            errors: final_errors,
+            output: Some(()),
+            errors,
             warnings
         };
     }
 
+    /// Prepends the `producer` comment to the `svg`.
+    /// `"--"` isn't allowed inside XML comments, so it's replaced with `"- -"`.
+    ///
+    /// ### Used internally.
+    fn svg_with_producer(svg: String, producer: &str) -> String {
+        // `--` isn't allowed inside comments, replacing can form new ones (`---`).
+        let mut producer = producer.to_string();
+        while producer.contains("--") {
+            producer = producer.replace("--", "- -");
+        }
+
+        let comment = format!("<!-- {producer} -->\n");
+        let mut output = String::with_capacity(comment.len() + svg.len());
+        output.push_str(&comment);
+        output.push_str(&svg);
+        return output;
+    }
+
+    /// Removes the `width` and `height` attributes from the root element of the `svg`,
+    /// keeping the `viewBox`, so the SVG scales with its container.
+    ///
+    /// ### Used internally.
+    fn svg_responsive(mut svg: String) -> String {
+        let Some(root_start) = svg.find("<svg ") else {
+            return svg;
+        };
+
+        for attribute in [" width=\"", " height=\""] {
+            let Some(root_end) = svg[root_start..].find('>').map(|end| root_start + end) else {
+                return svg;
+            };
+            let Some(start) = svg[root_start..root_end].find(attribute) else {
+                continue;
+            };
+            let start = root_start + start;
+            let value_start = start + attribute.len();
+            if let Some(value_end) = svg[value_start..].find('"') {
+                svg.replace_range(start..value_start + value_end + 1, "");
+            }
+        }
+
+        return svg;
+    }
+
     /// Compiles typst Document into a collection of SVG bytes and consumes `self`.
     ///
     /// One item for each page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
@@ -489,10 +2450,140 @@ impl Compiler {
     /// }
     /// ```
     pub fn compile_svg(self) -> CompilerOutput<Vec<Vec<u8>>> {
-        let background = self.background;
-        let page_background = Smart::Custom(Some(Paint::Solid(background)));
+        return self.compile_svg_ref();
+    }
 
-        let compiler_output: CompilerOutput<Document> = self.compile_document();
+    /// Compiles typst Document into a collection of SVG bytes **without** consuming `self`.
+    ///
+    /// Same as [compile_svg](Self::compile_svg), but the [Compiler] can be used again,
+    /// recompiling is incremental. Call [reset](Self::reset) between the runs to pick up
+    /// changed files.
+    ///
+    /// One item for each page. Returns [Vec\<Vec\<u8\>\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    pub fn compile_svg_ref(&self) -> CompilerOutput<Vec<Vec<u8>>> {
+        let compiler_output: CompilerOutput<Vec<(String, Size)>> = self.compile_svg_inner();
+
+        return CompilerOutput {
+            output: compiler_output.output
+                .map(|pages| pages.into_iter().map(|(svg, _)| svg.into_bytes()).collect()),
+            errors: compiler_output.errors,
+            warnings: compiler_output.warnings
+        };
+    }
+
+    /// Compiles typst Document into a collection of SVG strings and consumes `self`.
+    ///
+    /// Same as [compile_svg](Self::compile_svg), but SVGs are returned as [String]s,
+    /// handy for embedding them inline in HTML.
+    ///
+    /// One item for each page. Returns [Vec\<String\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Embeds all pages into an HTML page.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_svg_strings();
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     let html = format!("<html><body>{}</body></html>", pages.join("\n"));
+    ///     std::fs::write("./index.html", html)
+    ///         .expect("Couldn't write HTML");
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_svg_strings(self) -> CompilerOutput<Vec<String>> {
+        let compiler_output: CompilerOutput<Vec<(String, Size)>> = self.compile_svg_inner();
+
+        return CompilerOutput {
+            output: compiler_output.output
+                .map(|pages| pages.into_iter().map(|(svg, _)| svg).collect()),
+            errors: compiler_output.errors,
+            warnings: compiler_output.warnings
+        };
+    }
+
+    /// Compiles typst Document into a collection of SVG bytes with page dimensions
+    /// and consumes `self`.
+    ///
+    /// Dimensions are taken from the page frame (before serialization), so there's no need
+    /// to parse them out of the SVG. Useful for laying out SVGs with correct aspect ratios.
+    ///
+    /// One item for each page. Returns [Vec\<SvgPage\>](Vec) [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Embeds all pages into an HTML grid, keeping their aspect ratios.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_svg_with_dimensions();
+    ///
+    /// if let Some(pages) = compiled.output {
+    ///     for page in pages {
+    ///         let ratio = page.width_pt / page.height_pt;
+    ///         println!("<div style=\"aspect-ratio: {ratio}\">{} bytes</div>", page.svg.len());
+    ///     }
+    /// } else {
+    ///     dbg!(compiled.errors); // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile_svg_with_dimensions(self) -> CompilerOutput<Vec<SvgPage>> {
+        let compiler_output: CompilerOutput<Vec<(String, Size)>> = self.compile_svg_inner();
+
+        let output: Option<Vec<SvgPage>> = compiler_output.output.map(|pages| {
+            pages
+                .into_iter()
+                .map(|(svg, size)| SvgPage {
+                    svg: svg.into_bytes(),
+                    width_pt: size.x.to_pt(),
+                    height_pt: size.y.to_pt()
+                })
+                .collect()
+        });
+
+        return CompilerOutput {
+            output,
+            errors: compiler_output.errors,
+            warnings: compiler_output.warnings
+        };
+    }
+
+    /// Compiles typst Document into a collection of SVG strings with page [Size]s in points.
+    ///
+    /// ### Used internally.
+    fn compile_svg_inner(&self) -> CompilerOutput<Vec<(String, Size)>> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
         let errors = compiler_output.errors;
         let warnings = compiler_output.warnings;
 
@@ -505,73 +2596,327 @@ impl Compiler {
             }
         };
 
-        let final_pages: Vec<Vec<u8>>;
-        let final_errors: EcoVec<SourceDiagnostic>;
+        let (output, errors) = self.export_svg_pages(document.pages, errors);
 
-        // Sync compilation of pages.
-        #[cfg(not(feature = "parallel_compilation"))]
-        {
-            // Gets number of pages in a document and allocates memory upfront.
-            let pages_count = document.pages.len();
-            let mut pages_buffer: Vec<Vec<u8>> = vec![Vec::new(); pages_count];
-            let pages_errors = errors;
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
 
-            for (page_index, mut page) in document.pages.into_iter().enumerate() {
-                page.fill = page_background.clone();
-                let buf = typst_svg::svg(&page).into_bytes();
-                pages_buffer[page_index] = buf;
+    /// Encodes already compiled `pages` into a collection of SVG strings with page [Size]s
+    /// in points. Encoding errors are appended to `errors`.
+    ///
+    /// ### Used internally.
+    fn export_svg_pages(
+        &self,
+        pages: Vec<Page>,
+        errors: EcoVec<SourceDiagnostic>
+    ) -> (Option<Vec<(String, Size)>>, EcoVec<SourceDiagnostic>) {
+        let producer: &str = &self.producer;
+        let page_background = Smart::Custom(self.svg_background.map(Paint::Solid));
+        let responsive: bool = self.svg_responsive;
+
+        return Self::encode_pages(pages, errors, |_, mut page| {
+            page.fill = page_background.clone();
+
+            let size: Size = page.frame.size();
+            let mut svg = typst_svg::svg(&page);
+            if responsive {
+                svg = Self::svg_responsive(svg);
             }
+            Ok((Self::svg_with_producer(svg, producer), size))
+        });
+    }
 
-            final_pages = pages_buffer;
-            final_errors = pages_errors;
-        }
+    /// Compiles typst Document into the `format` chosen at runtime and consumes `self`.
+    ///
+    /// Same as calling [compile_pdf](Self::compile_pdf), [compile_png](Self::compile_png)
+    /// or [compile_svg](Self::compile_svg), handy when the format comes from a configuration.
+    ///
+    /// Returns [CompiledBytes] [CompilerOutput], [CompiledBytes::Document] for PDF and
+    /// [CompiledBytes::Pages] for PNG and SVG.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Compiles Document to the format requested by the client.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    /// let format = OutputFormat::Svg;
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile(format);
+    ///
+    /// match compiled.output {
+    ///     Some(CompiledBytes::Document(bytes)) => println!("{} bytes", bytes.len()),
+    ///     Some(CompiledBytes::Pages(pages)) => println!("{} pages", pages.len()),
+    ///     Some(_) => unreachable!(),
+    ///     None => { dbg!(compiled.errors); } // Compilation failed, show errors.
+    /// }
+    /// ```
+    pub fn compile(self, format: OutputFormat) -> CompilerOutput<CompiledBytes> {
+        let (output, errors, warnings) = match format {
+            OutputFormat::Pdf => {
+                let compiled: CompilerOutput<Vec<u8>> = self.compile_pdf_ref();
+                (compiled.output.map(CompiledBytes::Document), compiled.errors, compiled.warnings)
+            },
+            OutputFormat::Png => {
+                let compiled: CompilerOutput<Vec<Vec<u8>>> = self.compile_png_ref();
+                (compiled.output.map(CompiledBytes::Pages), compiled.errors, compiled.warnings)
+            },
+            OutputFormat::Svg => {
+                let compiled: CompilerOutput<Vec<Vec<u8>>> = self.compile_svg_ref();
+                (compiled.output.map(CompiledBytes::Pages), compiled.errors, compiled.warnings)
+            }
+        };
 
-        // Parallel compilation of pages.
-        #[cfg(feature = "parallel_compilation")]
-        {
-            use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
 
-            // Gets number of pages in a document and allocates memory upfront.
-            // Because of parallel SVG encoding, the pages buffer needs to be inside a mutex.
-            // The same applies to errors.
-            let pages_count = document.pages.len();
-            let shared_pages_buffer: Mutex<Vec<Vec<u8>>> = Mutex::new(
-                vec![Vec::new(); pages_count]
-            );
-            let shared_errors: Mutex<EcoVec<SourceDiagnostic>> = Mutex::new(errors);
+    /// Compiles typst Document **once** and exports it into every format from `formats`.
+    /// Consumes `self`.
+    ///
+    /// Same as calling [compile_pdf](Self::compile_pdf), [compile_png](Self::compile_png)
+    /// and [compile_svg](Self::compile_svg) on separate compilers, but the sources are
+    /// compiled (and fonts loaded) only once. Formats that weren't requested are `None`.
+    /// Duplicated formats are exported only once.
+    ///
+    /// Returns [MultiOutput]. If the compilation failed, all outputs are `None`. If exporting
+    /// to one format failed, only that output is `None` and the error is added to `errors`.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) to PNGs or SVGs,
+    /// the compiler tries to encode/convert images to bytes in parallel with `rayon`.
+    /// To sync up compiled pages, again it uses **SYNC** mutex. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Compiles Document to PDF and SVG for a documentation site.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_all(&[OutputFormat::Pdf, OutputFormat::Svg]);
+    ///
+    /// if let Some(pdf) = compiled.pdf {
+    ///     std::fs::write("./main.pdf", pdf).expect("Couldn't write PDF");
+    /// }
+    /// if let Some(pages) = compiled.svg {
+    ///     pages.iter().enumerate().for_each(|(index, page)| {
+    ///         let filename = format!("./output/{index}.svg");
+    ///         std::fs::write(filename, page)
+    ///             .expect("Couldn't write SVG");
+    ///     });
+    /// }
+    /// dbg!(compiled.errors);
+    /// ```
+    pub fn compile_all(self, formats: &[OutputFormat]) -> MultiOutput {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let mut multi_output = MultiOutput {
+            errors: compiler_output.errors,
+            warnings: compiler_output.warnings,
+            ..Default::default()
+        };
 
-            let _ = document
-                .pages
-                .into_par_iter() // Tries to encode pages to SVG in parallel.
-                .enumerate()
-                .map(|(page_index, mut page)| {
-                    page.fill = page_background.clone();
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return multi_output // 'Bubbles up' `None` variant.
+        };
 
-                    // Write SVG to the shared buffer.
-                    let buf = typst_svg::svg(&page).into_bytes();
-                    {
-                        shared_pages_buffer.lock()[page_index] = buf;
-                    }
-            }).collect::<Vec<()>>();
+        if formats.contains(&OutputFormat::Pdf) {
+            let exported: CompilerOutput<Vec<u8>> = self.export_pdf_from(&document);
+            multi_output.pdf = exported.output;
+            multi_output.errors.extend(exported.errors);
+            multi_output.warnings.extend(exported.warnings);
+        }
 
-            // Takes pages and errors from the mutex
-            final_pages = shared_pages_buffer.into_inner();
-            final_errors = shared_errors.into_inner();
+        if formats.contains(&OutputFormat::Png) {
+            let errors = std::mem::take(&mut multi_output.errors);
+            let (output, errors) =
+                self.export_png_pages(document.pages.clone(), 0, &[], &[], errors);
+            multi_output.png = output;
+            multi_output.errors = errors;
         }
 
-        // Checks if any `page vector` is empty, which indicates
-        // that error occured. Discards all pages if any encoutered an error.
-        let encoding_error_occured = final_pages.iter().any(|x| x.is_empty());
-        let output: Option<Vec<Vec<u8>>> = if encoding_error_occured {
-            None
-        } else {
-            Some(final_pages)
-        };
+        if formats.contains(&OutputFormat::Svg) {
+            let errors = std::mem::take(&mut multi_output.errors);
+            let (output, errors) = self.export_svg_pages(document.pages, errors);
+            multi_output.svg = output
+                .map(|pages| pages.into_iter().map(|(svg, _)| svg.into_bytes()).collect());
+            multi_output.errors = errors;
+        }
 
-        return CompilerOutput {
-            output,
-            errors: final_errors,
-            warnings
+        // Export warnings are denied too.
+        if self.deny_warnings && !multi_output.warnings.is_empty() {
+            multi_output.pdf = None;
+            multi_output.png = None;
+            multi_output.svg = None;
+            let warnings = multi_output.warnings.clone();
+            multi_output.errors.extend(warnings);
+        }
+
+        return multi_output;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::builder::CompilerBuilder;
+    use crate::parameters::Input;
+
+    /// Creates an empty temporary directory, unique for the test.
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("typst-lib-wrapper-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("Couldn't create the temporary directory");
+        return dir;
+    }
+
+    /// Returns media box widths of the PDF pages, in page tree order.
+    fn pdf_page_widths(pdf: &[u8]) -> Vec<String> {
+        let pdf = String::from_utf8_lossy(pdf);
+        return pdf
+            .split("/Type /Page\n")
+            .skip(1)
+            .filter_map(|page| page.split("/MediaBox [0 0 ").nth(1))
+            .filter_map(|media_box| media_box.split(' ').next())
+            .map(String::from)
+            .collect();
+    }
+
+    #[test]
+    fn pdf_pages_are_indexed_from_zero() {
+        let compile = |ranges: Vec<RangeInclusive<usize>>| {
+            let content = "#for width in (101, 102, 103) { page(width: width * 1pt)[] }";
+            let compiler = CompilerBuilder::with_content_input(content)
+                .build()
+                .expect("Couldn't build the compiler");
+            let output = compiler.compile_pdf_pages(ranges);
+            assert!(output.errors.is_empty(), "{:?}", output.errors);
+            return pdf_page_widths(&output.output.expect("Missing PDF"));
         };
+
+        assert_eq!(compile(vec![1..=1]), ["102"]);
+        assert_eq!(compile(vec![0..=0, 2..=5]), ["101", "103"]);
+        assert_eq!(compile(vec![1..=2, 0..=1]), ["101", "102", "103"]);
+
+        // Out-of-bounds and reversed ranges select no pages.
+        assert!(compile(vec![3..=10]).is_empty());
+        assert!(compile(vec![RangeInclusive::new(2, 0)]).is_empty());
+    }
+
+    #[test]
+    fn formerly_reserved_file_name_compiles() {
+        let name = "CUSTOM_SOURCE_CONTENT_INPUT_IN_MEMORY_FILE";
+        let root = temp_dir("formerly-reserved-name");
+        std::fs::write(root.join(name), "#metadata(\"reserved\") <reserved>").unwrap();
+
+        let inputs = [
+            Input::file(name, &root),
+            Input::content_with_root(format!("#include \"{name}\""), &root),
+            Input::content_with_root(format!("#include \"/{name}\""), &root)
+        ];
+        for input in inputs {
+            for offset in [0, 4] {
+                let compiler = CompilerBuilder::with_input(input.clone())
+                    .with_page_number_offset(offset)
+                    .build()
+                    .expect("Couldn't build the compiler");
+                let output = compiler.query("<reserved>");
+
+                assert!(output.errors.is_empty(), "{input:?} ({offset}): {:?}", output.errors);
+                assert_eq!(output.output.map(|values| values.len()), Some(1));
+            }
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    // `<` and `>` aren't allowed in Windows file names.
+    #[cfg(unix)]
+    #[test]
+    fn wrapped_content_entry_doesnt_hide_root_files() {
+        let root = temp_dir("wrapped-content-entry");
+        std::fs::write(root.join("<content>"), "Real file").unwrap();
+
+        let compiler = CompilerBuilder::with_input(Input::content_with_root(
+            "#include \"/<content>\"",
+            &root
+        ))
+            .with_page_number_offset(4)
+            .build()
+            .expect("Couldn't build the compiler");
+        let output = compiler.compile_document_ref();
+
+        assert!(output.errors.is_empty(), "{:?}", output.errors);
+        let real_file = FileId::new(None, VirtualPath::new("<content>"));
+        assert!(compiler.accessed_files().contains(&real_file));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn render_page_into_reuses_matching_pixmap() {
+        let compiler = CompilerBuilder::with_content_input(
+            "#set page(width: 100pt, height: 50pt)\nA #pagebreak() B"
+        )
+            .with_ppi(144.0)
+            .build()
+            .expect("Couldn't build the compiler");
+
+        let mut pixmap = Pixmap::new(200, 100).unwrap();
+        for page_index in 0..2 {
+            pixmap.fill(tiny_skia::Color::TRANSPARENT);
+            let output = compiler.render_page_into(page_index, &mut pixmap);
+            assert!(output.output.is_some(), "{:?}", output.errors);
+            // Background is opaque white.
+            assert_eq!(pixmap.pixel(0, 0).map(|pixel| pixel.alpha()), Some(255));
+        }
+
+        let output = compiler.render_page_into(2, &mut pixmap);
+        assert!(output.output.is_none() && output.errors.len() == 1);
+
+        // Mismatched pixmap is left untouched.
+        let mut pixmap = Pixmap::new(100, 50).unwrap();
+        let output = compiler.render_page_into(0, &mut pixmap);
+        assert!(output.output.is_none() && output.errors.len() == 1);
+        assert!(pixmap.data().iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn svg_producer_comment_has_no_double_hyphens() {
+        for producer in ["a--b", "a---b", "a----b", "--", "---"] {
+            let svg = Compiler::svg_with_producer(String::from("<svg></svg>"), producer);
+            let comment = svg
+                .strip_prefix("<!-- ")
+                .and_then(|svg| svg.strip_suffix(" -->\n<svg></svg>"))
+                .expect("Missing producer comment");
+
+            assert!(!comment.contains("--"), "{producer:?} escaped as {comment:?}");
+        }
     }
 }
@@ -0,0 +1,403 @@
+//! Provides a way to resolve [SourceDiagnostic] spans into human friendly positions.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use typst::diag::{Severity, SourceDiagnostic};
+use typst::World;
+use typst_syntax::{FileId, Span};
+
+use crate::compiler::Compiler;
+use crate::parameters::CompilerOutput;
+
+/// [SourceDiagnostic] with its [Span] resolved to a file and a position in that file.
+///
+/// Created with [CompilerOutput::resolved_iter]. Position fields are `None` if the
+/// diagnostic isn't attached to any source (detached span) or if the source couldn't
+/// be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedDiagnostic {
+    /// Whether the diagnostic is an error or a warning.
+    pub severity: Severity,
+    /// Diagnostic message describing the problem.
+    pub message: String,
+    /// Additional hints on how to avoid or work around the problem.
+    pub hints: Vec<String>,
+    /// Original span of the diagnostic.
+    pub span: Span,
+    /// Path of the file, relative to the project root. Package files are prefixed
+    /// with the package specification, e.g. `"@preview/example:0.1.0/lib.typ"`.
+    pub file: Option<String>,
+    /// Line number, **starting from 1**.
+    pub line: Option<usize>,
+    /// Column number (in characters), **starting from 1**.
+    pub column: Option<usize>
+}
+
+/// Serializable form of [ResolvedDiagnostic], for example for rendering diagnostics
+/// in a web UI as JSON. Requires `serde` feature.
+///
+/// Created with [CompilerOutput::serializable_diagnostics]. [Span] is left out and severity
+/// is serialized as `"error"` or `"warning"`.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SerializableDiagnostic {
+    /// Diagnostic message describing the problem.
+    pub message: String,
+    /// Either `"error"` or `"warning"`.
+    pub severity: String,
+    /// Path of the file, relative to the project root. Package files are prefixed
+    /// with the package specification, e.g. `"@preview/example:0.1.0/lib.typ"`.
+    pub file: Option<String>,
+    /// Line number, **starting from 1**.
+    pub line: Option<usize>,
+    /// Column number (in characters), **starting from 1**.
+    pub column: Option<usize>,
+    /// Additional hints on how to avoid or work around the problem.
+    pub hints: Vec<String>
+}
+
+#[cfg(feature = "serde")]
+impl From<ResolvedDiagnostic> for SerializableDiagnostic {
+    fn from(value: ResolvedDiagnostic) -> Self {
+        let severity = match value.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning"
+        };
+
+        return Self {
+            message: value.message,
+            severity: severity.to_string(),
+            file: value.file,
+            line: value.line,
+            column: value.column,
+            hints: value.hints
+        };
+    }
+}
+
+impl ResolvedDiagnostic {
+    /// Formats the [FileId] as a path, prefixed with the package specification if the
+    /// file belongs to a package.
+    ///
+    /// ### Used internally.
+    fn file_name(id: FileId) -> String {
+        let path = id.vpath().as_rootless_path().to_string_lossy();
+        return match id.package() {
+            Some(package) => format!("{package}/{path}"),
+            None => path.into_owned()
+        };
+    }
+
+    /// Resolves the `diagnostic` span with the `compiler` sources.
+    ///
+    /// ### Used internally.
+    pub(crate) fn resolve(diagnostic: &SourceDiagnostic, compiler: &Compiler) -> Self {
+        let id: Option<FileId> = diagnostic.span.id();
+
+        let mut line: Option<usize> = None;
+        let mut column: Option<usize> = None;
+        if let Some(source) = id.and_then(|id| compiler.source(id).ok()) {
+            if let Some(start) = source.range(diagnostic.span).map(|range| range.start) {
+                line = source.byte_to_line(start).map(|x| x + 1);
+                column = source.byte_to_column(start).map(|x| x + 1);
+            }
+        }
+
+        return Self {
+            severity: diagnostic.severity,
+            message: diagnostic.message.to_string(),
+            hints: diagnostic.hints.iter().map(|hint| hint.to_string()).collect(),
+            span: diagnostic.span,
+            file: id.map(Self::file_name),
+            line,
+            column
+        };
+    }
+}
+
+/// Source line of a diagnostic, used to show the context of the diagnostic.
+///
+/// ### Used internally.
+struct SourceSnippet {
+    /// Line number, **starting from 1**.
+    line: usize,
+    /// Text of the line, without the line break.
+    text: String,
+    /// Underlined characters of the line. Spans over multiple lines are cut off
+    /// at the end of the first line.
+    underline: Range<usize>
+}
+
+impl SourceSnippet {
+    /// Loads the source line of the `span` with the `compiler` sources.
+    /// Returns `None` if the span is detached or the source couldn't be loaded.
+    ///
+    /// ### Used internally.
+    fn resolve(span: Span, compiler: &Compiler) -> Option<Self> {
+        let source = compiler.source(span.id()?).ok()?;
+        let range = source.range(span)?;
+        let line = source.byte_to_line(range.start)?;
+        let line_range = source.line_to_range(line)?;
+
+        let text = source.text()[line_range.clone()].trim_end_matches(['\n', '\r']);
+        let start = source.byte_to_column(range.start)?;
+        let end_byte = range.end.clamp(range.start, line_range.start + text.len());
+        let end = start + source.text()[range.start..end_byte].chars().count();
+
+        return Some(Self {
+            line: line + 1,
+            text: text.to_string(),
+            underline: start..end.max(start + 1)
+        });
+    }
+}
+
+/// Escape sequences used to style the diagnostic report, empty for plain text.
+///
+/// ### Used internally.
+struct Palette {
+    /// Style of the `error` label and its underline.
+    error: &'static str,
+    /// Style of the `warning` label and its underline.
+    warning: &'static str,
+    /// Style of the diagnostic message.
+    message: &'static str,
+    /// Style of the gutter (line numbers, borders) and the `hint` label.
+    gutter: &'static str,
+    /// Style of the span text within the source line.
+    span: &'static str,
+    /// Resets all styles.
+    reset: &'static str
+}
+
+impl Palette {
+    /// No styles, plain text.
+    const PLAIN: Palette = Palette {
+        error: "", warning: "", message: "", gutter: "", span: "", reset: ""
+    };
+
+    /// ANSI styles, similar to the typst CLI.
+    #[cfg(feature = "ansi_diagnostics")]
+    const ANSI: Palette = Palette {
+        error: "\x1b[1;91m",
+        warning: "\x1b[1;93m",
+        message: "\x1b[1m",
+        gutter: "\x1b[94m",
+        span: "\x1b[4m",
+        reset: "\x1b[0m"
+    };
+}
+
+/// Appends a human readable report of the `diagnostic` to the `report`, styled with
+/// the `palette`.
+///
+/// ### Used internally.
+fn render_diagnostic(
+    report: &mut String,
+    diagnostic: &ResolvedDiagnostic,
+    compiler: &Compiler,
+    palette: &Palette
+) {
+    let Palette { message: bold, gutter: blue, span: underlined, reset, .. } = palette;
+    let (severity, color) = match diagnostic.severity {
+        Severity::Error => ("error", palette.error),
+        Severity::Warning => ("warning", palette.warning)
+    };
+    let _ = writeln!(report, "{color}{severity}{reset}{bold}: {}{reset}", diagnostic.message);
+
+    let snippet = SourceSnippet::resolve(diagnostic.span, compiler);
+    let gutter = snippet.as_ref().map_or(1, |snippet| snippet.line.to_string().len());
+    let padding = " ".repeat(gutter);
+
+    if let (Some(file), Some(line), Some(column)) =
+        (&diagnostic.file, diagnostic.line, diagnostic.column)
+    {
+        let _ = writeln!(report, "{padding} {blue}┌─{reset} {file}:{line}:{column}");
+    }
+
+    if let Some(snippet) = snippet {
+        let Range { start, end } = snippet.underline;
+        let before: String = snippet.text.chars().take(start).collect();
+        let span: String = snippet.text.chars().skip(start).take(end - start).collect();
+        let after: String = snippet.text.chars().skip(end).collect();
+
+        // Tabs are kept, so the underline stays aligned with the text.
+        let indent: String = before.chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        let underline = "^".repeat(end - start);
+
+        let line = snippet.line;
+        let _ = writeln!(report, "{padding} {blue}│{reset}");
+        let _ = writeln!(report, "{blue}{line} │{reset} {before}{underlined}{span}{reset}{after}");
+        let _ = writeln!(report, "{padding} {blue}│{reset} {indent}{color}{underline}{reset}");
+    }
+
+    for hint in &diagnostic.hints {
+        let _ = writeln!(report, "{padding} {blue}= hint{reset}: {hint}");
+    }
+}
+
+/// Renders all `diagnostics` into a report, separated by an empty line.
+///
+/// ### Used internally.
+fn render_diagnostics(
+    diagnostics: impl Iterator<Item = ResolvedDiagnostic>,
+    compiler: &Compiler,
+    palette: &Palette
+) -> String {
+    let mut report = String::new();
+    for (index, diagnostic) in diagnostics.enumerate() {
+        if index > 0 {
+            report.push('\n');
+        }
+        render_diagnostic(&mut report, &diagnostic, compiler, palette);
+    }
+
+    return report;
+}
+
+impl<T> CompilerOutput<T> {
+    /// Iterates over `errors` and then `warnings`, resolving each diagnostic [Span] into
+    /// a file, line and column **lazily**, only when the item is consumed.
+    ///
+    /// Useful when only the first few diagnostics are displayed, unconsumed diagnostics
+    /// are never resolved.
+    ///
+    /// `compiler` must be the same [Compiler] that produced this output, so the method works
+    /// with methods that **don't** consume the [Compiler] (for example
+    /// [broken_references](Compiler::broken_references)).
+    ///
+    /// # Example
+    /// Prints the first 10 diagnostics.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.broken_references();
+    ///
+    /// for diagnostic in compiled.resolved_iter(&compiler).take(10) {
+    ///     let file = diagnostic.file.unwrap_or_default();
+    ///     let line = diagnostic.line.unwrap_or_default();
+    ///     println!("{file}:{line}: {}", diagnostic.message);
+    /// }
+    /// ```
+    pub fn resolved_iter<'a>(
+        &'a self,
+        compiler: &'a Compiler
+    ) -> impl Iterator<Item = ResolvedDiagnostic> + 'a {
+        return self.errors
+            .iter()
+            .chain(self.warnings.iter())
+            .map(move |diagnostic| ResolvedDiagnostic::resolve(diagnostic, compiler));
+    }
+
+    /// Resolves all `errors` and then all `warnings` into [ResolvedDiagnostic]s with a file,
+    /// line and column, ready to be presented to the end user.
+    ///
+    /// Same as [resolved_iter](Self::resolved_iter), but collects everything at once.
+    /// `compiler` must be the same [Compiler] that produced this output.
+    ///
+    /// # Example
+    /// Prints all diagnostics.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_pdf_ref();
+    ///
+    /// for diagnostic in compiled.formatted_diagnostics(&compiler) {
+    ///     let file = diagnostic.file.unwrap_or_default();
+    ///     let line = diagnostic.line.unwrap_or_default();
+    ///     let column = diagnostic.column.unwrap_or_default();
+    ///     println!("{:?} {file}:{line}:{column}: {}", diagnostic.severity, diagnostic.message);
+    /// }
+    /// ```
+    pub fn formatted_diagnostics(&self, compiler: &Compiler) -> Vec<ResolvedDiagnostic> {
+        return self.resolved_iter(compiler).collect();
+    }
+
+    /// Renders all `errors` and then all `warnings` into a human readable report, similar
+    /// to the typst CLI. Each diagnostic has its severity, message, `file:line:column`,
+    /// the source line with the underlined span and hints. Diagnostics are separated
+    /// by an empty line.
+    ///
+    /// `compiler` must be the same [Compiler] that produced this output.
+    ///
+    /// # Example
+    /// Prints the report if the compilation failed.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_pdf_ref();
+    ///
+    /// if compiled.output.is_none() {
+    ///     eprintln!("{}", compiled.render_diagnostics(&compiler));
+    /// }
+    /// ```
+    pub fn render_diagnostics(&self, compiler: &Compiler) -> String {
+        return render_diagnostics(self.resolved_iter(compiler), compiler, &Palette::PLAIN);
+    }
+
+    /// Same as [render_diagnostics](Self::render_diagnostics), but the report is colored with
+    /// ANSI escape sequences for terminals, like the typst CLI: severity labels are colored
+    /// and spans are underlined. Requires `ansi_diagnostics` feature.
+    ///
+    /// If `no_color` is `true`, the report is plain text (for example when the output
+    /// is piped to a file, or if the `NO_COLOR` environment variable is set).
+    ///
+    /// # Example
+    /// Prints the colored report, unless `stderr` isn't a terminal.
+    /// ```
+    /// use std::io::IsTerminal;
+    ///
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_pdf_ref();
+    ///
+    /// let no_color = !std::io::stderr().is_terminal();
+    /// eprintln!("{}", compiled.render_diagnostics_ansi(&compiler, no_color));
+    /// ```
+    #[cfg(feature = "ansi_diagnostics")]
+    pub fn render_diagnostics_ansi(&self, compiler: &Compiler, no_color: bool) -> String {
+        let palette = if no_color { &Palette::PLAIN } else { &Palette::ANSI };
+        return render_diagnostics(self.resolved_iter(compiler), compiler, palette);
+    }
+
+    /// Resolves all `errors` and then all `warnings` into [SerializableDiagnostic]s.
+    /// Requires `serde` feature.
+    ///
+    /// `compiler` must be the same [Compiler] that produced this output.
+    ///
+    /// # Example
+    /// Serializes diagnostics to JSON (with `serde_json`).
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let compiled = compiler.compile_pdf_ref();
+    ///
+    /// let diagnostics = compiled.serializable_diagnostics(&compiler);
+    /// let json = serde_json::to_string(&diagnostics).expect("Couldn't serialize");
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn serializable_diagnostics(&self, compiler: &Compiler) -> Vec<SerializableDiagnostic> {
+        return self.resolved_iter(compiler).map(SerializableDiagnostic::from).collect();
+    }
+}
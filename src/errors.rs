@@ -4,6 +4,7 @@ use std::path::PathBuf;
 
 use thiserror::Error;
 use typst::diag::{FileError, PackageError};
+use typst_syntax::package::PackageSpec;
 
 pub type WrapperResult<T> = Result<T, WrapperError>;
 
@@ -12,10 +13,6 @@ pub type WrapperResult<T> = Result<T, WrapperError>;
 #[error("{0}")]
 pub enum WrapperError {
 
-    /// Forbidden filename/path
-    #[error("Used filename/path with forbidden text/contents. Please check ReadMe for more info.")]
-    ForbiddenFilenamePathText,
-
     /// Shouldn't happen, but just in case. \
     /// Uninitialized access to [FontCache](crate::fonts::FontCache).
     #[error("Accessing uninitialized font storage")]
@@ -23,12 +20,18 @@ pub enum WrapperError {
 
     // Font errors
 
-    /// Error loading font face.
-    #[error("Cound't load font face with path: {0}")]
+    /// Error loading font face, or the font file doesn't contain any font face.
+    #[error("Couldn't load font face with path: {0}")]
     FontFaceLoadingError(PathBuf),
     /// Error loading font from file system.
-    #[error("Coudn't load font: {0}")]
-    FontLoadingError(std::io::Error),
+    #[error("Couldn't load font with path: {0}: {1}")]
+    FontLoadingError(PathBuf, std::io::Error),
+    /// Font file format isn't supported.
+    #[error("Unsupported font format (WOFF2) with path: {0}, convert it to TTF/OTF or WOFF")]
+    UnsupportedFontFormat(PathBuf),
+    /// Font data (bytes) couldn't be loaded.
+    #[error("Couldn't load font from bytes: {0}")]
+    InvalidFontData(String),
 
     // Input errors
 
@@ -39,6 +42,24 @@ pub enum WrapperError {
     #[error("Input `{0}` outside of root `{1}`")]
     InputOutsideRoot(PathBuf, PathBuf),
 
+    // Configuration errors
+
+    /// Invalid numbering pattern.
+    #[error("Invalid numbering pattern `{0}`")]
+    InvalidNumberingPattern(String),
+    /// Invalid locale (malformed language code).
+    #[error("Invalid locale `{0}`")]
+    InvalidLocale(String),
+    /// Incompatible combination of PDF standards.
+    #[error("Invalid PDF standards: {0}")]
+    InvalidPdfStandards(String),
+    /// Custom data key doesn't start with an underscore (strict custom data).
+    #[error("Custom data key `{0}` doesn't start with `_`")]
+    InvalidCustomDataKey(String),
+    /// Custom data key shadows a standard library symbol (strict custom data).
+    #[error("Custom data key `{0}` shadows a standard library symbol")]
+    ShadowedStandardSymbol(String),
+
     /// Wrapper around [std::io::Error].
     #[error("IO: `{0}`")]
     Io(std::io::Error),
@@ -47,15 +68,54 @@ pub enum WrapperError {
     #[error("HTTP: `{0}`")]
     Http(Box<ureq::Error>),
 
+    // Compilation errors
+
+    /// Typst layout didn't stabilize within the (fixed) number of layout iterations.
+    #[error(
+        "Layout did not converge: {0}. \
+        Hint: simplify the document, check if any states, counters or queries \
+        (for example in an outline) are updating themselves"
+    )]
+    LayoutDidNotConverge(String),
+
     /// Wrapper around typst [FileError].
     #[error("File: `{0}`")]
     File(FileError),
     /// Wrapper arount typst [PackageError].
     #[error("Package: `{0}`")]
     Package(PackageError),
+    /// Package download failed (package specification, URL, underlying error message).
+    #[error("Package `{0}` download from `{1}` failed: {2}")]
+    PackageDownloadFailed(String, String, String),
+    /// Downloaded package doesn't match the expected SHA-256 checksum
+    /// (package specification, expected checksum, actual checksum).
+    #[error("Package `{0}` checksum mismatch, expected SHA-256 `{1}`, got `{2}`")]
+    PackageChecksumMismatch(String, String, String),
+    /// Some packages couldn't be prefetched, holds each failed package with its error.
+    #[error("Couldn't prefetch {} package(s)", .0.len())]
+    PackagePrefetchFailed(Vec<(PackageSpec, WrapperError)>),
 
 }
 
+impl WrapperError {
+    /// Converts the error into typst [PackageError], so it can be reported during
+    /// compilation. [WrapperError::Package] is unwrapped, [WrapperError::Http] and
+    /// [WrapperError::PackageDownloadFailed] are reported as a network failure,
+    /// other errors are described.
+    ///
+    /// ### Used internally.
+    pub(crate) fn into_package_error(self) -> PackageError {
+        return match self {
+            Self::Package(err) => err,
+            Self::Http(err) => PackageError::NetworkFailed(Some(ecow::eco_format!("{err}"))),
+            err @ Self::PackageDownloadFailed(..) => {
+                PackageError::NetworkFailed(Some(ecow::eco_format!("{err}")))
+            },
+            err => PackageError::Other(Some(ecow::eco_format!("{err}")))
+        };
+    }
+}
+
 impl From<std::io::Error> for WrapperError {
     fn from(value: std::io::Error) -> Self {
         Self::Io(value)
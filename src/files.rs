@@ -3,13 +3,14 @@
 //!
 //! ### Used internally.
 
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use typst::diag::{FileError, FileResult};
 use typst::foundations::Bytes;
 use typst_syntax::{FileId, Source};
 
-use crate::package::prepare_package;
+use crate::errors::WrapperError;
+use crate::package::{prepare_package, PackageSettings};
 
 /// Same as [SlotCell](https://docs.rs/crate/typst-cli/latest/source/src/world.rs)
 /// from [typst-cli](https://github.com/typst/typst/tree/main/crates/typst-cli).
@@ -36,6 +37,9 @@ impl<T: Clone> LazyCell<T> {
     }
 
     /// Gets the contents of the cell or initialize them.
+    ///
+    /// Within one compilation (until [reset](Self::reset)) the file is loaded only once.
+    /// Afterwards it's loaded again, but processed again only if its fingerprint changed.
     fn get_or_init(
         &mut self,
         load: impl FnOnce() -> FileResult<Vec<u8>>,
@@ -66,6 +70,10 @@ impl<T: Clone> LazyCell<T> {
         value
     }
 
+    /// Marks the cell as not accessed, so the next access checks the file for changes.
+    fn reset(&mut self) {
+        self.accessed = false;
+    }
 }
 
 /// Same as [FileSlot](https://docs.rs/crate/typst-cli/latest/source/src/world.rs)
@@ -78,6 +86,8 @@ impl<T: Clone> LazyCell<T> {
 pub(crate) struct LazyFile {
     /// The slot's file id.
     id: FileId,
+    /// Contents of an in-memory (virtual) file, the file system isn't touched if set.
+    contents: Option<Vec<u8>>,
     /// The lazily loaded and incrementally updated source file.
     source: LazyCell<Source>,
     /// The lazily loaded raw byte buffer.
@@ -99,17 +109,60 @@ impl LazyFile {
     /// Resolves the path of a file id on the system, downloading a package if necessary.
     ///
     /// Determine the root path relative to which the file path will be resolved.
+    /// Project files are resolved against the `project_root` first, then against each of
+    /// the `additional_roots`. Paths escaping the `project_root` (`"../shared/file.typ"`)
+    /// are allowed if they lead into one of the `additional_roots`. The first existing file
+    /// is used, otherwise the path within the `project_root` is returned (so the error
+    /// is reported for it).
     fn system_path(
         project_root: &Path,
+        additional_roots: &[PathBuf],
         id: FileId,
-        http_client: &ureq::Agent
+        packages: &PackageSettings
     ) -> FileResult<PathBuf> {
         if let Some(spec) = id.package() {
-            let package_path: PathBuf = prepare_package(spec, http_client)?;
+            let package_path: PathBuf = prepare_package(spec, packages)
+                .map_err(WrapperError::into_package_error)?;
             return id.vpath().resolve(&package_path).ok_or(FileError::AccessDenied);
         }
 
-        return id.vpath().resolve(project_root).ok_or(FileError::AccessDenied);
+        let project_path: Option<PathBuf> = id.vpath().resolve(project_root);
+        if project_path.as_ref().is_some_and(|path| path.exists()) {
+            return project_path.ok_or(FileError::AccessDenied);
+        }
+
+        for root in additional_roots.iter() {
+            if let Some(path) = id.vpath().resolve(root).filter(|path| path.exists()) {
+                return Ok(path);
+            }
+        }
+
+        if !additional_roots.is_empty() {
+            let escaped_path = Self::resolve_lexically(project_root, id);
+            let in_additional_root = additional_roots
+                .iter()
+                .any(|root| escaped_path.starts_with(root));
+            if in_additional_root && escaped_path.exists() {
+                return Ok(escaped_path);
+            }
+        }
+
+        return project_path.ok_or(FileError::AccessDenied);
+    }
+
+    /// Resolves the virtual path of `id` against the `root`, allowing it to escape the `root`
+    /// with `".."` components. Doesn't touch the file system.
+    fn resolve_lexically(root: &Path, id: FileId) -> PathBuf {
+        let mut path = root.to_path_buf();
+        for component in id.vpath().as_rootless_path().components() {
+            match component {
+                Component::ParentDir => { path.pop(); },
+                Component::Normal(_) => path.push(component),
+                _ => {}
+            }
+        }
+
+        return path;
     }
 
     /// Decode UTF-8 with an optional BOM.
@@ -122,22 +175,79 @@ impl LazyFile {
     pub(crate) fn new(id: FileId) -> Self {
         Self {
             id,
+            contents: None,
             source: LazyCell::new(),
             file: LazyCell::new()
         }
     }
 
+    /// Create a new slot for an in-memory (virtual) file with `contents`.
+    pub(crate) fn new_virtual(id: FileId, contents: Vec<u8>) -> Self {
+        Self {
+            id,
+            contents: Some(contents),
+            source: LazyCell::new(),
+            file: LazyCell::new()
+        }
+    }
+
+    /// Loads the raw contents of the file, from memory if it's a virtual file,
+    /// otherwise from disk. Will download packages if necessary.
+    fn load(
+        id: FileId,
+        contents: &Option<Vec<u8>>,
+        project_root: &Path,
+        additional_roots: &[PathBuf],
+        packages: &PackageSettings
+    ) -> FileResult<Vec<u8>> {
+        if let Some(contents) = contents {
+            return Ok(contents.clone());
+        }
+
+        let path = Self::system_path(project_root, additional_roots, id, packages)?;
+        return Self::read_from_disk(&path);
+    }
+
+    /// Marks the file as not accessed in the current compilation.
+    ///
+    /// Processed data and fingerprints are kept, so the file is read again on the next access,
+    /// but unchanged contents aren't processed again.
+    pub(crate) fn reset(&mut self) {
+        self.source.reset();
+        self.file.reset();
+    }
+
+    /// Drops processed data and fingerprints, so the file is read and processed again
+    /// on the next access. Contents of a virtual file are kept.
+    pub(crate) fn clear(&mut self) {
+        self.source = LazyCell::new();
+        self.file = LazyCell::new();
+    }
+
+    /// Whether the file is an in-memory (virtual) file.
+    pub(crate) fn is_virtual(&self) -> bool {
+        self.contents.is_some()
+    }
+
+    /// Whether the file (source or bytes) was accessed since the last reset.
+    pub(crate) fn accessed(&self) -> bool {
+        self.source.accessed || self.file.accessed
+    }
+
+    /// Combined hash of the raw contents (or access errors) of the loaded source and bytes.
+    pub(crate) fn fingerprint(&self) -> u128 {
+        typst_utils::hash128(&(self.source.fingerprint, self.file.fingerprint))
+    }
+
     /// Retrieve the source for this file. Will download packages if necessary.
     pub(crate) fn source(
         &mut self,
         project_root: &Path,
-        http_client: &ureq::Agent
+        additional_roots: &[PathBuf],
+        packages: &PackageSettings
     ) -> FileResult<Source> {
         self.source.get_or_init(
-            || {
-                let path = Self::system_path(project_root, self.id, http_client)?;
-                Self::read_from_disk(&path)
-            },
+            || Self::load(self.id, &self.contents, project_root, additional_roots, packages),
 
             |data, prev| {
                 let text = Self::decode_utf8(&data)?;
@@ -155,13 +265,11 @@ impl LazyFile {
     pub(crate) fn file(
         &mut self,
         project_root: &Path,
-        http_client: &ureq::Agent
+        additional_roots: &[PathBuf],
+        packages: &PackageSettings
     ) -> FileResult<Bytes> {
         self.file.get_or_init(
-            || {
-                let path = Self::system_path(project_root, self.id, http_client)?;
-                Self::read_from_disk(&path)
-            },
+            || Self::load(self.id, &self.contents, project_root, additional_roots, packages),
 
             |data, _| Ok(data.into())
         )
@@ -1,22 +1,26 @@
 //! Provides a way to interract with the global [FontCache].
 
+use std::io::Read;
 use std::path::PathBuf;
 use std::sync::OnceLock;
 
 use fontdb::{Database, Source as FontSource};
 use parking_lot::{const_mutex, Mutex};
 use typst::foundations::Bytes;
-use typst::text::{Font, FontBook, FontInfo};
+use typst::text::{Font, FontBook, FontInfo, FontStyle};
 
 use crate::errors::{WrapperError, WrapperResult};
+use crate::parameters::{FontCacheStats, FontEntry};
+use crate::woff;
 
 /// Holds details about the location of a font and lazily the font itself.
 ///
 /// External docs: [FontSlot](https://docs.rs/crate/typst-cli/0.11.0/source/src/fonts.rs)
 #[derive(Debug, Clone)]
 pub(crate) struct LazyFont {
-    /// The path at which the font can be found on the system.
-    path: PathBuf,
+    /// The path at which the font can be found on the system. `None` if the font
+    /// was loaded from memory (embedded or inserted bytes), its data is always resident.
+    path: Option<PathBuf>,
     /// The index of the font in its collection. Zero if the path does not point to a collection.
     index: u32,
     /// The lazily loaded font.
@@ -28,11 +32,14 @@ pub(crate) struct LazyFont {
 impl LazyFont {
     /// Gets the font data.
     ///
-    /// If the font is not loaded, loads the font from disk.
-    /// Returns `None` is error occurred.
+    /// If the font is not loaded, loads the font from disk (decompressing WOFF fonts).
+    /// Returns `None` is error occurred or if the font is in-memory font that was dropped.
     pub(crate) fn get(&self) -> Option<Font> {
         let font = self.font.get_or_init(|| {
-            let raw_font: Vec<u8> = std::fs::read(&self.path).ok()?;
+            let mut raw_font: Vec<u8> = std::fs::read(self.path.as_ref()?).ok()?;
+            if raw_font.starts_with(woff::WOFF_SIGNATURE) {
+                raw_font = woff::decode(&raw_font).ok()?;
+            }
             let bytes: Bytes = Bytes::from(raw_font);
             Font::new(bytes, self.index)
         });
@@ -159,11 +166,55 @@ impl FontCache {
         return Ok(cached_font_bytes);
     }
 
+    /// Returns [FontCacheStats] of the [FontCache]: number of all, loaded and embedded fonts
+    /// and size of the loaded font data, collected with a single lock.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache] Mutex. This Mutex is **NOT ASYNC**
+    /// so keep that in mind. Use **'blocking task'** provided by your runtime
+    /// if you wish to use it in an async environment.
+    ///
+    /// # Example
+    /// Logs the cache health.
+    /// ```
+    /// let stats = FontCache::stats().expect("Cache error");
+    /// println!(
+    ///     "{}/{} fonts loaded ({} embedded), {} bytes",
+    ///     stats.loaded, stats.total, stats.embedded, stats.loaded_bytes
+    /// );
+    /// ```
+    pub fn stats() -> WrapperResult<FontCacheStats> {
+        let mut font_cache_mutex = FONT_CACHE.lock();
+        let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
+
+        let mut stats = FontCacheStats {
+            total: font_cache.fonts.len(),
+            loaded: 0,
+            embedded: 0,
+            loaded_bytes: 0
+        };
+
+        for lazyfont in font_cache.fonts.iter() {
+            if lazyfont.embedded {
+                stats.embedded += 1;
+            }
+            if let Some(Some(font)) = lazyfont.font.get() {
+                stats.loaded += 1;
+                stats.loaded_bytes += font.data().len();
+            }
+        }
+
+        return Ok(stats);
+    }
+
     /// Clears the [FontCache] by dropping all the lazily loaded font data.
     ///
     /// If you wish to drop embedded font data set `include_embedded_fonts` to `true`.
     /// It is advised to set this to `false`, because you can 'irreversably' unload them.
     ///
+    /// Fonts inserted from bytes (see [insert_bytes](Self::insert_bytes)) are never dropped,
+    /// they can't be reloaded.
+    ///
     /// # Note / Warning
     /// If you choose to clear font data for embedded fonts, mind that you will
     /// probably irreversably make them inaccessible. They are loaded on [FontCache]
@@ -186,9 +237,11 @@ impl FontCache {
         let mut font_cache_mutex = FONT_CACHE.lock();
         let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
 
+        // Fonts inserted from bytes can't be reloaded, so they are always kept.
         for lazyfont in font_cache.fonts
             .iter_mut()
             .filter(|x| x.embedded == include_embedded_fonts)
+            .filter(|x| x.embedded || x.path.is_some())
         {
             drop(lazyfont.font.take());
         }
@@ -196,6 +249,152 @@ impl FontCache {
         return Ok(());
     }
 
+    /// Returns distinct font family names available in the [FontCache], sorted
+    /// alphabetically (case-insensitive).
+    ///
+    /// Useful for validating font names (`#set text(font: ...)`) before compiling.
+    /// Fonts aren't loaded, only the already discovered metadata is read.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache] Mutex. This Mutex is **NOT ASYNC**
+    /// so keep that in mind. Use **'blocking task'** provided by your runtime
+    /// if you wish to use it in an async environment.
+    ///
+    /// # Example
+    /// ```
+    /// let families = FontCache::list_families().expect("Cache error");
+    /// if !families.iter().any(|family| family.eq_ignore_ascii_case("Libertinus Serif")) {
+    ///     eprintln!("Font 'Libertinus Serif' isn't available");
+    /// }
+    /// ```
+    pub fn list_families() -> WrapperResult<Vec<String>> {
+        let mut font_cache_mutex = FONT_CACHE.lock();
+        let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
+
+        let families: Vec<String> = font_cache.book
+            .families()
+            .map(|(family, _)| family.to_string())
+            .collect();
+
+        return Ok(families);
+    }
+
+    /// Checks if the font family `name` is available in the [FontCache].
+    ///
+    /// Family names are matched case-insensitively, same as typst does when selecting fonts.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache] Mutex. This Mutex is **NOT ASYNC**
+    /// so keep that in mind. Use **'blocking task'** provided by your runtime
+    /// if you wish to use it in an async environment.
+    ///
+    /// # Example
+    /// Falls back to the default font if "Fira Sans" isn't available.
+    /// ```
+    /// let font = match FontCache::contains_family("Fira Sans") {
+    ///     Ok(true) => "Fira Sans",
+    ///     _ => "Libertinus Serif"
+    /// };
+    /// let content = format!("#set text(font: \"{font}\")\n= Hello");
+    /// ```
+    pub fn contains_family(name: &str) -> WrapperResult<bool> {
+        let mut font_cache_mutex = FONT_CACHE.lock();
+        let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
+
+        let contains = font_cache.book
+            .select_family(&name.to_lowercase())
+            .next()
+            .is_some();
+
+        return Ok(contains);
+    }
+
+    /// Returns all fonts (faces) available in the [FontCache] with their family, style,
+    /// weight and whether they are embedded, in the order they were discovered.
+    ///
+    /// Useful for building font pickers. Fonts aren't loaded, only the already discovered
+    /// metadata is read.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache] Mutex. This Mutex is **NOT ASYNC**
+    /// so keep that in mind. Use **'blocking task'** provided by your runtime
+    /// if you wish to use it in an async environment.
+    ///
+    /// # Example
+    /// ```
+    /// let fonts = FontCache::list_fonts().expect("Cache error");
+    /// for font in fonts {
+    ///     println!("{} ({}, {})", font.family, font.style, font.weight);
+    /// }
+    /// ```
+    pub fn list_fonts() -> WrapperResult<Vec<FontEntry>> {
+        let mut font_cache_mutex = FONT_CACHE.lock();
+        let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
+
+        // Book and fonts are always pushed together, so the indices match.
+        let fonts: Vec<FontEntry> = font_cache.fonts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, lazy_font)| {
+                let info: &FontInfo = font_cache.book.info(index)?;
+                let style = match info.variant.style {
+                    FontStyle::Normal => "normal",
+                    FontStyle::Italic => "italic",
+                    FontStyle::Oblique => "oblique"
+                };
+
+                Some(FontEntry {
+                    family: info.family.clone(),
+                    style: style.to_string(),
+                    weight: info.variant.weight.to_number(),
+                    embedded: lazy_font.embedded
+                })
+            })
+            .collect();
+
+        return Ok(fonts);
+    }
+
+    /// Drops the lazily loaded font data of all fonts of the family `name` and returns
+    /// the number of dropped fonts (faces).
+    ///
+    /// Family names are matched case-insensitively. Font metadata stays in the [FontCache],
+    /// so dropped fonts are lazily loaded again when needed. Embedded fonts and fonts inserted
+    /// from bytes (see [insert_bytes](Self::insert_bytes)) can't be reloaded, they are kept.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache] Mutex. This Mutex is **NOT ASYNC**
+    /// so keep that in mind. Use **'blocking task'** provided by your runtime
+    /// if you wish to use it in an async environment.
+    ///
+    /// # Example
+    /// Releases memory of a large font family after a batch job.
+    /// ```
+    /// let dropped = FontCache::remove_family("Noto Sans CJK SC").expect("Cache error");
+    /// println!("Dropped {dropped} fonts");
+    /// ```
+    pub fn remove_family(name: &str) -> WrapperResult<usize> {
+        let mut font_cache_mutex = FONT_CACHE.lock();
+        let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
+
+        let indices: Vec<usize> = font_cache.book.select_family(&name.to_lowercase()).collect();
+
+        let mut dropped: usize = 0;
+        for index in indices {
+            let Some(lazyfont) = font_cache.fonts.get_mut(index) else {
+                continue;
+            };
+
+            if !lazyfont.embedded && lazyfont.path.is_some()
+                && lazyfont.font.take().flatten().is_some()
+            {
+                dropped += 1;
+            }
+        }
+
+        return Ok(dropped);
+    }
+
     /// Updates the cache if detects that there are new lazily loaded fonts.
     ///
     /// - `new_fonts`: After compilation maybe we loaded some [lazy fonts](LazyFont).
@@ -211,73 +410,61 @@ impl FontCache {
         let mut font_cache_mutex = FONT_CACHE.lock();
         let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
 
-        // Counts all loaded fonts currently in cache.
-        let cache_loaded_fonts_count = font_cache.fonts
-            .iter()
-            .filter(|x| x.font.get().is_some())
-            .count();
+        let mut updated: usize = 0;
 
-        // Counts all potentially newly loaded fonts by some typst compilation.
-        let new_loaded_fonts_count = new_fonts
-            .iter()
-            .filter(|x| x.font.get().is_some())
-            .count();
+        // Iterate only over new 'loaded' fonts. Counting loaded fonts can't be used to skip
+        // the update, other compilations could have loaded different fonts in the meantime.
+        for (position, mut new_font) in new_fonts.into_iter().enumerate() {
 
-        // If there is not difference, don't update the cache.
-        // This skips the heavy operation of searching and updating new fonts.
-        if cache_loaded_fonts_count == new_loaded_fonts_count {
-            return Ok(0);
-        }
+            // Skips fonts that weren't loaded.
+            let Some(Some(font)) = new_font.font.take() else {
+                continue;
+            };
+            let info = font.info();
+
+            // Compiler fonts are cloned from the cache, so the same font is usually at
+            // the same position. Otherwise, search for the font metadata in the cache.
+            let cache_index: Option<usize> = match font_cache.fonts.get(position) {
+                Some(cached) if new_font.path.is_some()
+                    && cached.path == new_font.path
+                    && cached.index == new_font.index => Some(position),
+                _ => font_cache.book.select(&info.family.to_lowercase(), info.variant)
+            };
 
-        let mut updated: usize = 0;
+            if let Some(cache_index) = cache_index {
+                // Font metadata found in cache.
+
+                // This should always return `Some` variant,
+                // because we know have a valid 'cache_index'.
+                if let Some(found) = font_cache.fonts.get_mut(cache_index) {
 
-        // Iterate only over new 'loaded' fonts.
-        for mut new_font in new_fonts.into_iter().filter(|x| x.font.get().is_some()) {
-
-            // We know that this will always return `Some` variant.
-            // We could `filter_map` the vector, but we need the `LazyFont` (`new_font`).
-            if let Some(Some(font)) = new_font.font.take() {
-                let info = font.info();
-
-                // Search for the font metadata if it exists in the cache.
-                if let Some(cache_index) = font_cache
-                    .book
-                    .select(&info.family.to_lowercase(), info.variant)
-                {
-                    // Font metadata found in cache.
-
-                    // This should always return `Some` variant,
-                    // because we know have a valid 'cache_index'.
-                    if let Some(found) = font_cache.fonts.get_mut(cache_index) {
-
-                        // We need to replace the font data if it doesn't exists.
-                        // Otherwise, leave it alone (already cached);
-                        if found.font.get().is_none() {
-                            let _ = found.font.take(); // Discards value, just in case.
-                            if found.font.set(Some(font)).is_ok() {
-                                updated += 1; // Successfully updated the cache.
-                            }
+                    // We need to replace the font data if it doesn't exists.
+                    // Otherwise, leave it alone (already cached);
+                    if found.font.get().is_none() {
+                        let _ = found.font.take(); // Discards value, just in case.
+                        if found.font.set(Some(font)).is_ok() {
+                            updated += 1; // Successfully updated the cache.
                         }
                     }
+                }
 
-                } else {
-                    // Font not found in cache.
+            } else {
+                // Font not found in cache.
 
-                    // The cache cleaning doesn't erase metadata, so this can happen
-                    // if the cache was reinitialized again with different configuration.
-                    // We can just add the missing font into cache again.
+                // The cache cleaning doesn't erase metadata, so this can happen
+                // if the cache was reinitialized again with different configuration.
+                // We can just add the missing font into cache again.
 
-                    // Clone the font info as late as possible, only if needed.
-                    let font_info = info.clone();
+                // Clone the font info as late as possible, only if needed.
+                let font_info = info.clone();
 
-                    // Return font back into 'LazyFont'.
-                    new_font.font = OnceLock::from(Some(font));
-                    // Push font info into font cache.
-                    font_cache.book.push(font_info);
-                    font_cache.fonts.push(new_font);
+                // Return font back into 'LazyFont'.
+                new_font.font = OnceLock::from(Some(font));
+                // Push font info into font cache.
+                font_cache.book.push(font_info);
+                font_cache.fonts.push(new_font);
 
-                    updated += 1;
-                }
+                updated += 1;
             }
         }
 
@@ -340,7 +527,7 @@ impl FontCache {
             if let Some(font_info) = info {
                 font_cache.book.push(font_info);
                 font_cache.fonts.push(LazyFont {
-                    path: path.clone(),
+                    path: Some(path.clone()),
                     index: face.index,
                     font: OnceLock::new(),
                     embedded: false,
@@ -351,10 +538,72 @@ impl FontCache {
         Ok(())
     }
 
+    /// Loads the font file at `path` into the `database`.
+    ///
+    /// WOFF fonts can't be loaded into the `database`, so they are decompressed and
+    /// their faces are inserted into the `font_cache` directly (still lazily loaded).
+    /// WOFF2 fonts aren't supported and return [WrapperError::UnsupportedFontFormat].
+    ///
+    /// Returns [WrapperError::FontLoadingError] if the file can't be read and
+    /// [WrapperError::FontFaceLoadingError] if it doesn't contain any font face.
+    ///
+    /// # Note / Warning
+    /// [Global font cache](FontCache) must be **LOCKED** before calling this function.
+    ///
+    /// ### Used internally.
+    fn load_font_file(
+        font_cache: &mut FontCache,
+        database: &mut Database,
+        path: PathBuf
+    ) -> WrapperResult<()> {
+        let mut signature = [0u8; 4];
+        // Files shorter than the signature are left to the `database`.
+        let _ = std::fs::File::open(&path).and_then(|mut file| file.read_exact(&mut signature));
+
+        if &signature == woff::WOFF2_SIGNATURE {
+            return Err(WrapperError::UnsupportedFontFormat(path));
+        }
+        if &signature != woff::WOFF_SIGNATURE {
+            // The `database` silently skips files without font faces.
+            let faces_count = database.len();
+            database
+                .load_font_file(&path)
+                .map_err(|err| WrapperError::FontLoadingError(path.clone(), err))?;
+
+            if database.len() == faces_count {
+                return Err(WrapperError::FontFaceLoadingError(path));
+            }
+            return Ok(());
+        }
+
+        let raw_font: Vec<u8> = std::fs::read(&path)
+            .map_err(|err| WrapperError::FontLoadingError(path.clone(), err))?;
+        let font_data: Vec<u8> = woff::decode(&raw_font)
+            .map_err(|_| WrapperError::FontFaceLoadingError(path.clone()))?;
+
+        let mut faces_count: usize = 0;
+        for (index, font_info) in FontInfo::iter(&font_data).enumerate() {
+            font_cache.book.push(font_info);
+            font_cache.fonts.push(LazyFont {
+                path: Some(path.clone()),
+                index: index as u32,
+                font: OnceLock::new(),
+                embedded: false,
+            });
+            faces_count += 1;
+        }
+
+        if faces_count == 0 {
+            return Err(WrapperError::FontFaceLoadingError(path));
+        }
+
+        return Ok(());
+    }
+
     /// Creates a lazy font and inserts it into [FontCache].
     ///
     /// - `font_path` - Anything that can be converted to [PathBuf] pointing
-    /// to a font file.
+    /// to a font file. TTF, OTF, TTC and WOFF fonts are supported.
     ///
     /// # Note / Warning
     /// ### Blocking Mutex
@@ -373,8 +622,7 @@ impl FontCache {
         let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
 
         let mut db = Database::new();
-        db.load_font_file(font_path.into())
-            .map_err(WrapperError::FontLoadingError)?;
+        Self::load_font_file(font_cache, &mut db, font_path.into())?;
 
         return Self::insert_from_database(font_cache, db);
     }
@@ -382,7 +630,7 @@ impl FontCache {
     /// For each font path in a [Vec] creates a lazy font and inserts it into [FontCache].
     ///
     /// - `font_paths` - [Vec] containing anything that can be converted into [PathBuf]
-    /// with each being a font path.
+    /// with each being a font path. TTF, OTF, TTC and WOFF fonts are supported.
     ///
     /// # Note / Warning
     /// ### Blocking Mutex
@@ -407,13 +655,112 @@ impl FontCache {
 
         let mut db = Database::new();
         for font_path in font_paths {
-            db.load_font_file(font_path.into())
-                .map_err(WrapperError::FontLoadingError)?;
+            Self::load_font_file(font_cache, &mut db, font_path.into())?;
         }
 
         return Self::insert_from_database(font_cache, db);
     }
 
+    /// Inserts all fonts (faces) from the font `data` into [FontCache].
+    ///
+    /// Useful for fonts bundled into the binary with `include_bytes!`. The font data stays
+    /// resident in memory, same as typst embedded fonts, it is **not** dropped by
+    /// [clear_cache](Self::clear_cache).
+    ///
+    /// - `data` - Font file bytes. TTF, OTF, TTC and WOFF fonts are supported.
+    ///
+    /// Returns [WrapperError::InvalidFontData] if the `data` doesn't contain any font.
+    ///
+    /// # Note / Warning
+    /// ### Blocking Mutex
+    /// Any operation on the [FontCache] will lock the Mutex. This mutex is **NOT ASYNC**
+    /// so keep that in mind. Use **'blocking task'** provided by your runtime
+    /// if you wish to use it in an async environment.
+    ///
+    /// # Example
+    /// Inserts a bundled font into [FontCache].
+    /// ```
+    /// static FONT: &[u8] = include_bytes!("../assets/fonts/times_new_roman.ttf");
+    ///
+    /// FontCache::insert_bytes(FONT.to_vec())
+    ///     .expect("Cache error");
+    /// ```
+    pub fn insert_bytes(data: Vec<u8>) -> WrapperResult<()> {
+        let mut font_cache_mutex = FONT_CACHE.lock();
+        let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
+
+        return Self::insert_from_bytes(font_cache, data);
+    }
+
+    /// For each font data in a [Vec] inserts all fonts (faces) into [FontCache].
+    ///
+    /// Same as [insert_bytes](Self::insert_bytes), but for multiple fonts. Fonts before
+    /// the first invalid font data are inserted.
+    ///
+    /// - `data` - [Vec] of font file bytes. TTF, OTF, TTC and WOFF fonts are supported.
+    ///
+    /// # Note / Warning
+    /// ### Blocking Mutex
+    /// Any operation on the [FontCache] will lock the Mutex. This mutex is **NOT ASYNC**
+    /// so keep that in mind. Use **'blocking task'** provided by your runtime
+    /// if you wish to use it in an async environment.
+    ///
+    /// # Example
+    /// Inserts bundled fonts into [FontCache].
+    /// ```
+    /// static REGULAR: &[u8] = include_bytes!("../assets/fonts/fira_sans_regular.ttf");
+    /// static BOLD: &[u8] = include_bytes!("../assets/fonts/fira_sans_bold.ttf");
+    ///
+    /// FontCache::insert_bytes_many(vec![REGULAR.to_vec(), BOLD.to_vec()])
+    ///     .expect("Cache error");
+    /// ```
+    pub fn insert_bytes_many(data: Vec<Vec<u8>>) -> WrapperResult<()> {
+        let mut font_cache_mutex = FONT_CACHE.lock();
+        let font_cache: &mut FontCache = Self::get_mut_or_init(&mut font_cache_mutex)?;
+
+        for font_data in data {
+            Self::insert_from_bytes(font_cache, font_data)?;
+        }
+
+        return Ok(());
+    }
+
+    /// Inserts all fonts (faces) from the font `data` into the [global font cache](FontCache),
+    /// already loaded. WOFF fonts are decompressed first.
+    ///
+    /// # Note / Warning
+    /// [Global font cache](FontCache) must be **LOCKED** before calling this function.
+    ///
+    /// ### Used internally.
+    fn insert_from_bytes(font_cache: &mut FontCache, mut data: Vec<u8>) -> WrapperResult<()> {
+        if data.starts_with(woff::WOFF2_SIGNATURE) {
+            return Err(WrapperError::InvalidFontData(String::from(
+                "unsupported font format (WOFF2), convert it to TTF/OTF or WOFF"
+            )));
+        }
+        if data.starts_with(woff::WOFF_SIGNATURE) {
+            data = woff::decode(&data).map_err(WrapperError::InvalidFontData)?;
+        }
+
+        let mut faces_count: usize = 0;
+        for (index, font) in Font::iter(Bytes::from(data)).enumerate() {
+            font_cache.book.push(font.info().clone());
+            font_cache.fonts.push(LazyFont {
+                path: None,
+                index: index as u32,
+                font: OnceLock::from(Some(font)),
+                embedded: false,
+            });
+            faces_count += 1;
+        }
+
+        if faces_count == 0 {
+            return Err(WrapperError::InvalidFontData(String::from("no font found")));
+        }
+
+        return Ok(());
+    }
+
     /// For each font in a directory creates a lazy font and inserts it into [FontCache].
     ///
     /// - `dir_path` - Anything that can be converted to [PathBuf] pointing to
@@ -523,7 +870,7 @@ impl FontCache {
             if let Some(font_info) = info {
                 book.push(font_info);
                 fonts.push(LazyFont {
-                    path: path.clone(),
+                    path: Some(path.clone()),
                     index: face.index,
                     font: OnceLock::new(),
                     embedded: false,
@@ -538,7 +885,7 @@ impl FontCache {
             for (i, font) in Font::iter(buffer).enumerate() {
                 book.push(font.info().clone());
                 fonts.push(LazyFont {
-                    path: PathBuf::new(),
+                    path: None,
                     index: i as u32,
                     font: OnceLock::from(Some(font)),
                     embedded: true,
@@ -649,3 +996,58 @@ impl FontCache {
         return Ok(());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::CompilerBuilder;
+    use crate::compiler::tests::temp_dir;
+
+    /// Returns the embedded "DejaVu Sans Mono" font renamed to "DejaWu Sans Mono", so it
+    /// doesn't collide with the embedded font (name table isn't checksum-validated).
+    fn renamed_font() -> Vec<u8> {
+        let mut data = typst_assets::fonts()
+            .find(|data| {
+                Font::new(Bytes::from_static(data), 0)
+                    .is_some_and(|font| font.info().family == "DejaVu Sans Mono")
+            })
+            .expect("Missing embedded font")
+            .to_vec();
+
+        // Family names are stored as ASCII (Macintosh) and UTF-16BE (Windows) strings.
+        let utf16 = |text: &str| text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        let names: [(Vec<u8>, Vec<u8>); 2] = [
+            (b"DejaVu".to_vec(), b"DejaWu".to_vec()),
+            (utf16("DejaVu"), utf16("DejaWu"))
+        ];
+        for (from, to) in names {
+            for start in 0..data.len() - from.len() {
+                if data[start..].starts_with(&from) {
+                    data[start..start + to.len()].copy_from_slice(&to);
+                }
+            }
+        }
+
+        return data;
+    }
+
+    #[test]
+    fn loaded_fonts_are_not_read_again() {
+        let dir = temp_dir("loaded-fonts");
+        let font_path = dir.join("DejaWuSansMono.ttf");
+        std::fs::write(&font_path, renamed_font()).unwrap();
+
+        let content = "#set text(font: \"DejaWu Sans Mono\")\nHello";
+        let compile = |builder: CompilerBuilder| {
+            let compiler = builder.build().expect("Couldn't build the compiler");
+            return compiler.plan().output.expect("Compilation failed").fonts;
+        };
+
+        let builder = CompilerBuilder::with_content_input(content).add_font_path(&font_path);
+        assert_eq!(compile(builder), ["DejaWu Sans Mono"]);
+
+        // Font can't be read from the disk anymore, only the cached data can be used.
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(compile(CompilerBuilder::with_content_input(content)), ["DejaWu Sans Mono"]);
+    }
+}
@@ -0,0 +1,1186 @@
+//! Provides a way to inspect compiled typst Document without exporting it.
+//!
+//! Methods here **don't** consume the [Compiler], they compile the document and
+//! examine the result (or the diagnostics).
+
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+
+use comemo::Track;
+use ecow::EcoVec;
+use typst::diag::SourceDiagnostic;
+use typst::eval::{eval_string, EvalMode};
+use typst::foundations::{
+    IntoValue, LocatableSelector, NativeElement, Scope, Smart, StyleChain, Value
+};
+use typst::layout::{Abs, Frame, FrameItem, Page, Point, Transform};
+use typst::model::{Document, HeadingElem};
+use typst::visualize::{Geometry, Paint, PathItem};
+use typst::World;
+use typst_syntax::package::{PackageManifest, PackageSpec};
+use typst_syntax::{ast, FileId, Span, SyntaxNode, VirtualPath};
+
+use crate::compiler::Compiler;
+use crate::parameters::{CompilePlan, CompilerOutput, ContentBounds, OutlineNode};
+
+impl Compiler {
+    /// Compiles the document and returns the number of pages, **without** rendering or
+    /// encoding them. Doesn't consume `self`.
+    ///
+    /// Useful for preparing progress bars or dispatching per-page work before exporting.
+    /// Typst memoizes compilation, so a follow-up `compile_` call with the same [Compiler]
+    /// reuses the layout.
+    ///
+    /// Returns [usize] [CompilerOutput].
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// if let Some(pages) = compiler.page_count().output {
+    ///     println!("Rendering {pages} pages...");
+    ///     let compiled = compiler.compile_png();
+    /// }
+    /// ```
+    pub fn page_count(&self) -> CompilerOutput<usize> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+
+        return CompilerOutput {
+            output: compiler_output.output.map(|document| document.pages.len()),
+            errors: compiler_output.errors,
+            warnings: compiler_output.warnings
+        };
+    }
+
+    /// Extracts the reference target from a diagnostic message, if the diagnostic
+    /// reports a missing label or a missing bibliography key.
+    ///
+    /// Typst reports them as:
+    /// - ``label `<intro>` does not exist in the document``
+    /// - ``key `knuth` does not exist in the bibliography``
+    ///
+    /// ### Used internally.
+    fn broken_reference_target(diagnostic: &SourceDiagnostic) -> Option<String> {
+        let message = diagnostic.message.as_str();
+        let is_broken_reference = (message.starts_with("label `")
+            && message.ends_with("` does not exist in the document"))
+            || (message.starts_with("key `")
+            && message.ends_with("` does not exist in the bibliography"));
+
+        if !is_broken_reference {
+            return None;
+        }
+
+        let start = message.find('`')? + 1;
+        let end = message.rfind('`')?;
+        return message.get(start..end).map(String::from);
+    }
+
+    /// Compiles the document and collects all reference/citation targets that couldn't
+    /// be resolved. Doesn't consume `self`.
+    ///
+    /// Labels are listed with angle brackets (`"<intro>"`), bibliography keys
+    /// without them (`"knuth"`). Each target is listed once, in order of appearance
+    /// in the diagnostics.
+    ///
+    /// Returns [Vec\<String\>](Vec) [CompilerOutput]. Broken references are compilation
+    /// errors in typst, so `output` is **always** `Some` (possibly empty) and `errors`
+    /// still contain all compilation errors, including the broken references.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// Fails the CI build if the document references something that doesn't exist.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let broken = compiler.broken_references();
+    ///
+    /// if let Some(targets) = broken.output {
+    ///     assert!(targets.is_empty(), "Broken references: {targets:?}");
+    /// }
+    /// ```
+    pub fn broken_references(&self) -> CompilerOutput<Vec<String>> {
+        let compiler_output = self.compile_document_ref();
+        let errors: EcoVec<SourceDiagnostic> = compiler_output.errors;
+        let warnings: EcoVec<SourceDiagnostic> = compiler_output.warnings;
+
+        let mut targets: Vec<String> = Vec::new();
+        let diagnostics = errors.iter().chain(warnings.iter());
+        for target in diagnostics.filter_map(Self::broken_reference_target) {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+
+        return CompilerOutput {
+            output: Some(targets),
+            errors,
+            warnings
+        };
+    }
+
+    /// Extracts the label from a diagnostic message, if the diagnostic reports
+    /// a label that occurs multiple times.
+    ///
+    /// Typst reports them as ``label `<intro>` occurs multiple times in the document``.
+    ///
+    /// ### Used internally.
+    fn duplicate_label_target(diagnostic: &SourceDiagnostic) -> Option<String> {
+        let message = diagnostic.message.as_str();
+        let is_duplicate_label = message.starts_with("label `")
+            && message.ends_with("` occurs multiple times in the document");
+
+        if !is_duplicate_label {
+            return None;
+        }
+
+        let start = message.find('`')? + 1;
+        let end = message.rfind('`')?;
+        return message.get(start..end).map(String::from);
+    }
+
+    /// Compiles the document and collects all labels attached to more than one element.
+    /// Doesn't consume `self`.
+    ///
+    /// Typst only reports a duplicate label if it's referenced (or queried), otherwise the
+    /// document compiles silently. This method scans all elements of the compiled document,
+    /// so unreferenced duplicates are found too.
+    ///
+    /// Labels are listed with angle brackets (`"<intro>"`), each label once, in order of
+    /// the first appearance in the document.
+    ///
+    /// Returns [Vec\<String\>](Vec) [CompilerOutput]. If the compilation fails (for
+    /// example, a duplicate label is referenced), labels are collected from the diagnostics,
+    /// so `output` is **always** `Some` (possibly empty).
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// Fails the CI build if the document contains duplicate labels.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let duplicates = compiler.duplicate_labels();
+    ///
+    /// if let Some(labels) = duplicates.output {
+    ///     assert!(labels.is_empty(), "Duplicate labels: {labels:?}");
+    /// }
+    /// ```
+    pub fn duplicate_labels(&self) -> CompilerOutput<Vec<String>> {
+        let compiler_output = self.compile_document_ref();
+        let errors: EcoVec<SourceDiagnostic> = compiler_output.errors;
+        let warnings: EcoVec<SourceDiagnostic> = compiler_output.warnings;
+
+        let mut labels: Vec<String> = Vec::new();
+        match compiler_output.output {
+            Some(document) => {
+                let mut seen: Vec<String> = Vec::new();
+                let elements = document.introspector.all();
+                for label in elements.filter_map(|element| element.label()) {
+                    let label = format!("<{}>", label.as_str());
+                    if !seen.contains(&label) {
+                        seen.push(label);
+                    } else if !labels.contains(&label) {
+                        labels.push(label);
+                    }
+                }
+            },
+            None => {
+                let diagnostics = errors.iter().chain(warnings.iter());
+                for label in diagnostics.filter_map(Self::duplicate_label_target) {
+                    if !labels.contains(&label) {
+                        labels.push(label);
+                    }
+                }
+            }
+        };
+
+        return CompilerOutput {
+            output: Some(labels),
+            errors,
+            warnings
+        };
+    }
+
+    /// Maximum difference between RGB channels for a pixel to still be considered gray.
+    /// Accounts for rounding during rasterization.
+    ///
+    /// ### Used internally.
+    const GRAYSCALE_TOLERANCE: u8 = 2;
+
+    /// Compiles the document, renders every page with configured PPI and background and
+    /// analyses rendered pixels with `analyze` function. Doesn't consume `self`.
+    ///
+    /// `analyze` receives raw RGBA (premultiplied alpha) pixel data of a page.
+    ///
+    /// ### Used internally.
+    fn analyze_pixels<T, F>(&self, analyze: F) -> CompilerOutput<Vec<T>>
+    where
+        T: Send,
+        F: Fn(&[u8]) -> T + Sync
+    {
+        let ppi = self.ppi / 72.0;
+        let page_background = Smart::Custom(Some(Paint::Solid(self.background)));
+
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let (output, errors) = Self::encode_pages(document.pages, errors, |_, mut page: Page| {
+            page.fill = page_background.clone();
+            let pixmap = typst_render::render(&page, ppi);
+            Ok(analyze(pixmap.data()))
+        });
+
+        return CompilerOutput {
+            output,
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles the document, renders it and checks if any page uses color (any pixel that
+    /// deviates from grayscale). Doesn't consume `self`.
+    ///
+    /// Pages are rendered with configured PPI and background, so a colored background
+    /// counts as color.
+    ///
+    /// Returns [bool] [CompilerOutput], `true` if the document uses color.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) pages are rendered
+    /// in parallel with `rayon`. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// Chooses between color and mono printing.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// if let Some(true) = compiler.uses_color().output {
+    ///     println!("Color print");
+    /// } else {
+    ///     println!("Mono print");
+    /// }
+    /// ```
+    pub fn uses_color(&self) -> CompilerOutput<bool> {
+        let compiler_output = self.analyze_pixels(|pixels| {
+            pixels.chunks_exact(4).any(|pixel| {
+                let max = pixel[0].max(pixel[1]).max(pixel[2]);
+                let min = pixel[0].min(pixel[1]).min(pixel[2]);
+                max - min > Self::GRAYSCALE_TOLERANCE
+            })
+        });
+
+        return CompilerOutput {
+            output: compiler_output.output.map(|pages| pages.into_iter().any(|x| x)),
+            errors: compiler_output.errors,
+            warnings: compiler_output.warnings
+        };
+    }
+
+    /// Compiles the document, renders it and estimates ink coverage of each page.
+    /// Doesn't consume `self`.
+    ///
+    /// Coverage is in range `0.0..=1.0`, where `0.0` is a blank (white) page and `1.0`
+    /// is a page completely covered in black. Pixels are composited over white, so
+    /// transparent background counts as blank paper. This is a rough estimate based on
+    /// pixel darkness, not a per-channel (CMYK) coverage.
+    ///
+    /// Returns [Vec\<f64\>](Vec) [CompilerOutput], one item for each page.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// If compiling with an opt-in feature (`"parallel_compilation"`) pages are rendered
+    /// in parallel with `rayon`. \
+    /// [On mixing `rayon` with `tokio`!](https://blog.dureuill.net/articles/dont-mix-rayon-tokio/)
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// if let Some(coverage) = compiler.ink_coverage().output {
+    ///     for (index, page) in coverage.iter().enumerate() {
+    ///         println!("Page {index}: {:.1}%", page * 100.0);
+    ///     }
+    /// }
+    /// ```
+    pub fn ink_coverage(&self) -> CompilerOutput<Vec<f64>> {
+        return self.analyze_pixels(|pixels| {
+            let pixels_count = pixels.len() / 4;
+            if pixels_count == 0 {
+                return 0.0;
+            }
+
+            let ink: f64 = pixels.chunks_exact(4).map(|pixel| {
+                // Premultiplied alpha, composite over white.
+                let uncovered = 255 - u32::from(pixel[3]);
+                let channels = pixel[..3].iter().map(|&c| u32::from(c) + uncovered).sum::<u32>();
+                1.0 - f64::from(channels) / (3.0 * 255.0)
+            }).sum();
+
+            ink / pixels_count as f64
+        });
+    }
+
+    /// Pushes the `node` as a child of the last node in `children` with a lower level,
+    /// or into `children` itself if there is no such node.
+    ///
+    /// ### Used internally.
+    fn push_outline_node(children: &mut Vec<OutlineNode>, node: OutlineNode) {
+        match children.last_mut() {
+            Some(last) if last.level < node.level => {
+                Self::push_outline_node(&mut last.children, node);
+            }
+            _ => children.push(node)
+        }
+    }
+
+    /// Compiles the document and returns elements matching the typst `selector`, same as
+    /// `typst query` CLI command. Doesn't consume `self`.
+    ///
+    /// `selector` is typst code evaluating to a selector, for example `"heading"`,
+    /// `"<label>"` or `"heading.where(level: 1)"`. Only locatable elements (headings,
+    /// figures, metadata, ...) can be queried.
+    ///
+    /// Returns [Vec\<Value\>](Vec) [CompilerOutput], one [Value] for each matched element
+    /// in document order. [Value] implements `serde::Serialize`, so it can be converted to
+    /// JSON directly. If the `selector` is invalid, `output` is `None` and the error is
+    /// added to `errors`.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// Extracts custom metadata, e.g. `#metadata((total: 42)) <invoice>`.
+    /// ```
+    /// let entry = "invoice.typ";
+    /// let root = "./templates";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    /// let queried = compiler.query("<invoice>");
+    ///
+    /// if let Some(values) = queried.output {
+    ///     let json = serde_json::to_string(&values).expect("Couldn't serialize");
+    ///     println!("{json}");
+    /// } else {
+    ///     dbg!(queried.errors); // Compilation or selector failed, show errors.
+    /// }
+    /// ```
+    pub fn query(&self, selector: &str) -> CompilerOutput<Vec<Value>> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let mut errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let world: &dyn World = self;
+        let evaluated = eval_string(
+            world.track(),
+            selector,
+            Span::detached(),
+            EvalMode::Code,
+            Scope::default()
+        );
+        let value: Value = match evaluated {
+            Ok(value) => value,
+            Err(selector_errors) => {
+                errors.extend(selector_errors);
+                return CompilerOutput { output: None, errors, warnings };
+            }
+        };
+
+        let selector: LocatableSelector = match value.cast() {
+            Ok(selector) => selector,
+            Err(err) => {
+                let hints = err.hints().to_vec();
+                let error = SourceDiagnostic::error(Span::detached(), err.message().clone())
+                    .with_hints(hints);
+                errors.push(error);
+                return CompilerOutput { output: None, errors, warnings };
+            }
+        };
+
+        let values: Vec<Value> = document.introspector
+            .query(&selector.0)
+            .into_iter()
+            .map(|element| element.into_value())
+            .collect();
+
+        return CompilerOutput {
+            output: Some(values),
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles the document and reconstructs the outline hierarchy from heading levels.
+    /// Doesn't consume `self`.
+    ///
+    /// Only outlined headings (`outlined: true`, the default) are included. A heading is
+    /// nested under the closest previous heading with a lower level, so skipped levels
+    /// (level 1 followed by level 3) are still nested. The tree matches the PDF bookmarks
+    /// structure viewers show.
+    ///
+    /// Returns [OutlineNode] [CompilerOutput], root node represents the whole document,
+    /// its `children` are top-level headings.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// Prints the table of contents.
+    /// ```
+    /// fn print(node: &OutlineNode) {
+    ///     for child in node.children.iter() {
+    ///         let indent = "  ".repeat(child.level - 1);
+    ///         println!("{indent}{} ... {}", child.title, child.page);
+    ///         print(child);
+    ///     }
+    /// }
+    ///
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// if let Some(outline) = compiler.outline_tree().output {
+    ///     print(&outline);
+    /// }
+    /// ```
+    pub fn outline_tree(&self) -> CompilerOutput<OutlineNode> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let mut root = OutlineNode {
+            title: document.info.title.as_deref().unwrap_or_default().to_string(),
+            level: 0,
+            page: 1,
+            children: Vec::new()
+        };
+
+        let introspector = &document.introspector;
+        for element in introspector.query(&HeadingElem::elem().select()).iter() {
+            let Some(heading) = element.to_packed::<HeadingElem>() else {
+                continue;
+            };
+            if !heading.outlined(StyleChain::default()) {
+                continue;
+            }
+
+            let page: usize = match element.location() {
+                Some(location) => introspector.page(location).get(),
+                None => continue
+            };
+            let node = OutlineNode {
+                title: heading.body().plain_text().trim().to_string(),
+                level: heading.resolve_level(StyleChain::default()).get(),
+                page,
+                children: Vec::new()
+            };
+
+            Self::push_outline_node(&mut root.children, node);
+        }
+
+        return CompilerOutput {
+            output: Some(root),
+            errors,
+            warnings
+        };
+    }
+
+    /// Collects literal paths of all `#import` and `#include` statements in the `node`
+    /// (recursively). Imports with dynamic (computed) paths are skipped.
+    ///
+    /// ### Used internally.
+    fn collect_import_paths(node: &SyntaxNode, paths: &mut Vec<String>) {
+        let source = if let Some(import) = node.cast::<ast::ModuleImport>() {
+            Some(import.source())
+        } else {
+            node.cast::<ast::ModuleInclude>().map(|include| include.source())
+        };
+
+        if let Some(ast::Expr::Str(path)) = source {
+            paths.push(path.get().to_string());
+        }
+
+        for child in node.children() {
+            Self::collect_import_paths(child, paths);
+        }
+    }
+
+    /// Resolves an import `path` written in the file `id` into a [FileId].
+    ///
+    /// Package imports (`"@preview/name:0.1.0"`) are resolved to the package entrypoint,
+    /// which requires reading the package manifest (and downloading the package).
+    ///
+    /// ### Used internally.
+    fn resolve_import(&self, id: FileId, path: &str) -> Option<FileId> {
+        if !path.starts_with('@') {
+            return Some(id.join(path));
+        }
+
+        let spec = PackageSpec::from_str(path).ok()?;
+        let manifest_id = FileId::new(Some(spec), VirtualPath::new("typst.toml"));
+        let bytes = self.file(manifest_id).ok()?;
+        let manifest: PackageManifest = toml::from_str(std::str::from_utf8(&bytes).ok()?).ok()?;
+
+        return Some(manifest_id.join(&manifest.package.entrypoint));
+    }
+
+    /// Compiles the document and builds its static import graph: which source files
+    /// import (or include) which. Doesn't consume `self`.
+    ///
+    /// Starting from the entry file, every reachable source is parsed and its `#import` and
+    /// `#include` statements with literal paths are resolved to [FileId]s. Package imports are
+    /// resolved to the package entrypoint. Dynamic paths (for example `#include name + ".typ"`)
+    /// can't be resolved statically and are skipped. Non-typst files (like `#import "data.csv"`)
+    /// can't be parsed, they're listed without dependencies.
+    ///
+    /// Returns [Vec\<(FileId, Vec\<FileId\>)\>](Vec) [CompilerOutput], one item for each
+    /// reachable file (starting with the entry) with its direct dependencies. `output` is
+    /// **always** `Some`, `errors` and `warnings` are from the compilation.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// Prints the import graph.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// if let Some(graph) = compiler.import_graph().output {
+    ///     for (file, dependencies) in graph {
+    ///         println!("{file:?} imports {dependencies:?}");
+    ///     }
+    /// }
+    /// ```
+    pub fn import_graph(&self) -> CompilerOutput<Vec<(FileId, Vec<FileId>)>> {
+        let compiler_output = self.compile_document_ref();
+
+        let mut graph: Vec<(FileId, Vec<FileId>)> = Vec::new();
+        let mut visited: Vec<FileId> = vec![self.entry.id()];
+        let mut queue: VecDeque<FileId> = VecDeque::from([self.entry.id()]);
+
+        while let Some(id) = queue.pop_front() {
+            let mut paths: Vec<String> = Vec::new();
+            if let Ok(source) = self.source(id) {
+                Self::collect_import_paths(source.root(), &mut paths);
+            }
+
+            let mut dependencies: Vec<FileId> = Vec::new();
+            for dependency in paths.iter().filter_map(|path| self.resolve_import(id, path)) {
+                if !dependencies.contains(&dependency) {
+                    dependencies.push(dependency);
+                }
+                if !visited.contains(&dependency) {
+                    visited.push(dependency);
+                    queue.push_back(dependency);
+                }
+            }
+
+            graph.push((id, dependencies));
+        }
+
+        return CompilerOutput {
+            output: Some(graph),
+            errors: compiler_output.errors,
+            warnings: compiler_output.warnings
+        };
+    }
+
+    /// Walks the `frame` (and its subframes) and collects families of the used fonts.
+    ///
+    /// ### Used internally.
+    fn collect_font_families(frame: &Frame, families: &mut Vec<String>) {
+        for (_, item) in frame.items() {
+            match item {
+                FrameItem::Group(group) => Self::collect_font_families(&group.frame, families),
+                FrameItem::Text(text) => {
+                    let family = &text.font.info().family;
+                    if !families.contains(family) {
+                        families.push(family.clone());
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    /// Compiles the document and reports everything the compilation needs, without exporting
+    /// anything (dry run). Doesn't consume `self`.
+    ///
+    /// Useful for capacity planning and security review. [CompilePlan] contains:
+    /// - page count,
+    /// - required packages (downloaded if necessary, as during any compilation),
+    /// - families of the fonts used in the document and requested font families that
+    /// aren't available,
+    /// - files accessed during the compilation (since the last [reset](Compiler::reset)).
+    ///
+    /// Returns [CompilePlan] [CompilerOutput], `output` is `None` if the compilation failed.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// if let Some(plan) = compiler.plan().output {
+    ///     println!("{} pages, {} files", plan.page_count, plan.files.len());
+    ///     if !plan.missing_fonts.is_empty() {
+    ///         eprintln!("Missing fonts: {:?}", plan.missing_fonts);
+    ///     }
+    /// }
+    /// ```
+    pub fn plan(&self) -> CompilerOutput<CompilePlan> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let files: Vec<FileId> = self.accessed_files();
+        let packages: Vec<PackageSpec> = self.used_packages();
+
+        let mut fonts: Vec<String> = Vec::new();
+        for page in document.pages.iter() {
+            Self::collect_font_families(&page.frame, &mut fonts);
+        }
+        fonts.sort();
+
+        let mut missing_fonts: Vec<String> = warnings
+            .iter()
+            .filter_map(|warning| warning.message.strip_prefix("unknown font family: "))
+            .map(String::from)
+            .collect();
+        missing_fonts.sort();
+        missing_fonts.dedup();
+
+        let plan = CompilePlan {
+            page_count: document.pages.len(),
+            packages,
+            fonts,
+            missing_fonts,
+            files
+        };
+
+        return CompilerOutput {
+            output: Some(plan),
+            errors,
+            warnings
+        };
+    }
+
+    /// Estimated size of the PDF structure that doesn't depend on the content (header,
+    /// catalog, document info, XMP metadata, cross-reference table, ...).
+    ///
+    /// ### Used internally.
+    const PDF_BASE_SIZE: usize = 4 * 1024;
+
+    /// Estimated size of a page object, its resources and content stream framing.
+    ///
+    /// ### Used internally.
+    const PDF_PAGE_SIZE: usize = 512;
+
+    /// Estimated content stream size of a single glyph (positioning and glyph id).
+    ///
+    /// ### Used internally.
+    const PDF_GLYPH_SIZE: usize = 8;
+
+    /// Estimated content stream size of a shape, link or image placement.
+    ///
+    /// ### Used internally.
+    const PDF_ITEM_SIZE: usize = 64;
+
+    /// Walks the `frame` (and its subframes), collects sizes of used fonts and images (keyed
+    /// by their hash, so each one is counted once) and returns the estimated size of the
+    /// content stream.
+    ///
+    /// ### Used internally.
+    fn estimate_frame(
+        frame: &Frame,
+        fonts: &mut HashMap<u128, usize>,
+        images: &mut HashMap<u128, usize>
+    ) -> usize {
+        let mut size: usize = 0;
+
+        for (_, item) in frame.items() {
+            size += match item {
+                FrameItem::Group(group) => {
+                    Self::PDF_ITEM_SIZE + Self::estimate_frame(&group.frame, fonts, images)
+                },
+                FrameItem::Text(text) => {
+                    fonts.insert(typst_utils::hash128(&text.font), text.font.data().len());
+                    text.glyphs.len() * Self::PDF_GLYPH_SIZE
+                },
+                FrameItem::Image(image, _, _) => {
+                    images.insert(typst_utils::hash128(image), image.data().len());
+                    Self::PDF_ITEM_SIZE
+                },
+                FrameItem::Shape(_, _) | FrameItem::Link(_, _) => Self::PDF_ITEM_SIZE,
+                FrameItem::Tag(_) => 0
+            };
+        }
+
+        return size;
+    }
+
+    /// Extends the `bounds` ([ContentBounds]) with the rectangle from `min` to `max`,
+    /// transformed with `ts` and limited to the `clip` bounds.
+    ///
+    /// ### Used internally.
+    fn extend_bounds(
+        bounds: &mut Option<ContentBounds>,
+        ts: Transform,
+        clip: Option<ContentBounds>,
+        min: Point,
+        max: Point
+    ) {
+        let corners = [
+            Point::new(min.x, min.y),
+            Point::new(max.x, min.y),
+            Point::new(min.x, max.y),
+            Point::new(max.x, max.y)
+        ];
+
+        let mut rect = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for corner in corners.map(|corner| corner.transform(ts)) {
+            rect.0 = rect.0.min(corner.x.to_pt());
+            rect.1 = rect.1.min(corner.y.to_pt());
+            rect.2 = rect.2.max(corner.x.to_pt());
+            rect.3 = rect.3.max(corner.y.to_pt());
+        }
+
+        if let Some(clip) = clip {
+            rect = (rect.0.max(clip.0), rect.1.max(clip.1), rect.2.min(clip.2), rect.3.min(clip.3));
+        }
+        // Fully clipped (or invalid) content isn't visible.
+        if !(rect.0 <= rect.2 && rect.1 <= rect.3) {
+            return;
+        }
+
+        *bounds = Some(match *bounds {
+            Some(b) => (b.0.min(rect.0), b.1.min(rect.1), b.2.max(rect.2), b.3.max(rect.3)),
+            None => rect
+        });
+    }
+
+    /// Walks the `frame` (and its subframes) transformed with `ts` and extends the `bounds`
+    /// with every visible item. Clipping groups limit the bounds of their content.
+    ///
+    /// Text is measured from the font ascender to the descender, shapes include half
+    /// of the stroke thickness, links and introspection tags are invisible.
+    ///
+    /// ### Used internally.
+    fn frame_bounds(
+        frame: &Frame,
+        ts: Transform,
+        clip: Option<ContentBounds>,
+        bounds: &mut Option<ContentBounds>
+    ) {
+        for (position, item) in frame.items() {
+            let ts = ts.pre_concat(Transform::translate(position.x, position.y));
+
+            match item {
+                FrameItem::Group(group) => {
+                    let group_ts = ts.pre_concat(group.transform);
+
+                    let mut group_clip = clip;
+                    if group.clip_path.is_some() {
+                        let mut frame_rect: Option<ContentBounds> = None;
+                        let size = group.frame.size();
+                        let max = Point::new(size.x, size.y);
+                        Self::extend_bounds(&mut frame_rect, group_ts, clip, Point::zero(), max);
+
+                        // Clipped to nothing, the whole group is invisible.
+                        let Some(frame_rect) = frame_rect else {
+                            continue;
+                        };
+                        group_clip = Some(frame_rect);
+                    }
+
+                    Self::frame_bounds(&group.frame, group_ts, group_clip, bounds);
+                },
+                FrameItem::Text(text) => {
+                    let metrics = text.font.metrics();
+                    let min = Point::new(Abs::zero(), -metrics.ascender.at(text.size));
+                    let max = Point::new(text.width(), -metrics.descender.at(text.size));
+                    Self::extend_bounds(bounds, ts, clip, min, max);
+                },
+                FrameItem::Shape(shape, _) => {
+                    let mut points: Vec<Point> = vec![Point::zero()];
+                    match &shape.geometry {
+                        Geometry::Line(end) => points.push(*end),
+                        Geometry::Rect(size) => points.push(Point::new(size.x, size.y)),
+                        Geometry::Path(path) => {
+                            points.clear();
+                            for path_item in path.0.iter() {
+                                match path_item {
+                                    PathItem::MoveTo(point) | PathItem::LineTo(point) => {
+                                        points.push(*point);
+                                    },
+                                    PathItem::CubicTo(first, second, end) => {
+                                        points.extend([*first, *second, *end]);
+                                    },
+                                    PathItem::ClosePath => {}
+                                }
+                            }
+                        }
+                    }
+                    if points.is_empty() {
+                        continue;
+                    }
+
+                    let half_stroke = shape.stroke
+                        .as_ref()
+                        .map(|stroke| stroke.thickness / 2.0)
+                        .unwrap_or_default();
+                    let mut min = Point::splat(Abs::inf());
+                    let mut max = Point::splat(-Abs::inf());
+                    for point in points {
+                        min = Point::new(min.x.min(point.x), min.y.min(point.y));
+                        max = Point::new(max.x.max(point.x), max.y.max(point.y));
+                    }
+
+                    let stroke = Point::splat(half_stroke);
+                    Self::extend_bounds(bounds, ts, clip, min - stroke, max + stroke);
+                },
+                FrameItem::Image(_, size, _) => {
+                    let max = Point::new(size.x, size.y);
+                    Self::extend_bounds(bounds, ts, clip, Point::zero(), max);
+                },
+                FrameItem::Link(_, _) | FrameItem::Tag(_) => {}
+            }
+        }
+    }
+
+    /// Compiles the document and computes the tight bounding box of the visible content
+    /// on each page, in points. Doesn't consume `self`.
+    ///
+    /// Bounding box is a tuple `(min_x, min_y, max_x, max_y)` measured from the top-left
+    /// corner of the page, `None` for pages without visible content. Page background
+    /// isn't content. Useful for cropping and for detecting content bleeding into
+    /// the margins (compare the bounds with the page size and margins).
+    ///
+    /// Bounds are computed from the layout (font ascender/descender, shape geometry,
+    /// image size), not from the rendered pixels, so they can be slightly larger than
+    /// the inked area (for example around glyphs).
+    ///
+    /// Returns [Vec\<Option\<ContentBounds\>\>](Vec) [CompilerOutput], one item for each page.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// Finds pages with content closer than 1cm (~28.35pt) to the page edge.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// if let Some(bounds) = compiler.content_bounds().output {
+    ///     for (index, page_bounds) in bounds.iter().enumerate() {
+    ///         if let Some((min_x, min_y, _, _)) = page_bounds {
+    ///             if *min_x < 28.35 || *min_y < 28.35 {
+    ///                 println!("Page {index}: content bleeds into the margin");
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn content_bounds(&self) -> CompilerOutput<Vec<Option<ContentBounds>>> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let bounds = document.pages
+            .iter()
+            .map(|page| {
+                let mut page_bounds: Option<ContentBounds> = None;
+                Self::frame_bounds(&page.frame, Transform::identity(), None, &mut page_bounds);
+                page_bounds
+            })
+            .collect();
+
+        return CompilerOutput {
+            output: Some(bounds),
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles (lays out) the document and estimates the size of the exported PDF in bytes,
+    /// **without** exporting it. Doesn't consume `self`.
+    ///
+    /// The estimate is a sum of:
+    /// - fixed PDF structure overhead and a fixed overhead for every page,
+    /// - content size estimated from the number of glyphs, shapes and images,
+    /// - full size of every used font (each font counted once),
+    /// - original size of every used image (each image counted once).
+    ///
+    /// # Accuracy
+    /// This is a rough, usually **upper** bound, meant for quota pre-checks (rejecting likely
+    /// oversized jobs early), not an exact size. Fonts are embedded as subsets and content
+    /// streams are compressed, so the real PDF is often several times smaller, especially
+    /// for text-only documents with large fonts (e.g. CJK). Raster images are embedded
+    /// close to their original size and dominate the estimate for image heavy documents,
+    /// where it's most accurate. SVG images are converted and may differ in either direction.
+    ///
+    /// Returns [usize] [CompilerOutput], estimated size in bytes.
+    ///
+    /// # Note / Warning
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// Rejects documents that would likely exceed 10 MB.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// if let Some(size) = compiler.estimate_pdf_size().output {
+    ///     if size > 10 * 1024 * 1024 {
+    ///         println!("Document is too large: ~{size} bytes");
+    ///     }
+    /// }
+    /// ```
+    pub fn estimate_pdf_size(&self) -> CompilerOutput<usize> {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_ref();
+        let errors = compiler_output.errors;
+        let warnings = compiler_output.warnings;
+
+        let document: Document = match compiler_output.output {
+            Some(doc) => doc,
+            None => return CompilerOutput {
+                output: None, // 'Bubbles up' `None` variant.
+                errors,
+                warnings
+            }
+        };
+
+        let mut fonts: HashMap<u128, usize> = HashMap::new();
+        let mut images: HashMap<u128, usize> = HashMap::new();
+
+        let mut size: usize = Self::PDF_BASE_SIZE;
+        for page in document.pages.iter() {
+            size += Self::PDF_PAGE_SIZE;
+            size += Self::estimate_frame(&page.frame, &mut fonts, &mut images);
+        }
+        size += fonts.values().sum::<usize>();
+        size += images.values().sum::<usize>();
+
+        return CompilerOutput {
+            output: Some(size),
+            errors,
+            warnings
+        };
+    }
+
+    /// Compiles the document and computes a fingerprint (hash) of all compilation inputs.
+    /// Doesn't consume `self`.
+    ///
+    /// Fingerprint combines:
+    /// - the entry source (path and content),
+    /// - every file accessed during compilation (since the last
+    /// [reset](crate::compiler::Compiler::reset), path and content), including package files,
+    /// so resolved package versions are covered,
+    /// - `sys_inputs`, custom data and other library configuration,
+    /// - fonts used in the document,
+    /// - output configuration (PDF standards, PPI, background, autocrop, producer, ...).
+    ///
+    /// Same fingerprint means the same inputs, so a cached output can be reused. The hash is
+    /// stable across runs (and processes) of the same library version, **unless** the library
+    /// configuration contains native values hashed by their address: a custom library
+    /// ([with_library](crate::builder::CompilerBuilder::with_library)), custom modules
+    /// ([with_module](crate::builder::CompilerBuilder::with_module)) or function-valued
+    /// custom data. With those, the fingerprint is only stable within one process.
+    ///
+    /// # Note / Warning
+    /// Accessed files are only known after compilation, so the document is compiled first.
+    /// Typst memoizes compilation, so a follow-up compilation with the same [Compiler] is cheap.
+    ///
+    /// Current date/time (`datetime.today()`, PDF timestamp) is **not** part of the
+    /// fingerprint.
+    ///
+    /// This will lock the [FontCache](crate::fonts::FontCache) Mutex and update it with lazily
+    /// loaded fonts. This mutex is **NOT ASYNC** so keep that in mind.
+    ///
+    /// # Example
+    /// Reuses cached PDF if the inputs didn't change.
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// let mut cache: HashMap<u128, Vec<u8>> = HashMap::new();
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// let fingerprint = compiler.input_fingerprint();
+    /// if !cache.contains_key(&fingerprint) {
+    ///     if let Some(pdf) = compiler.compile_pdf().output {
+    ///         cache.insert(fingerprint, pdf);
+    ///     }
+    /// }
+    /// ```
+    pub fn input_fingerprint(&self) -> u128 {
+        let compiler_output: CompilerOutput<Document> = self.compile_document_inner();
+
+        // Fonts used in the document.
+        let mut fonts: HashMap<u128, usize> = HashMap::new();
+        let mut images: HashMap<u128, usize> = HashMap::new();
+        if let Some(document) = compiler_output.output.as_ref() {
+            for page in document.pages.iter() {
+                Self::estimate_frame(&page.frame, &mut fonts, &mut images);
+            }
+        }
+        let mut fonts: Vec<u128> = fonts.into_keys().collect();
+        fonts.sort_unstable();
+
+        // Accessed files, identified by paths (file ids aren't stable across processes).
+        // Cached but unused files (for example unused virtual files) don't affect the output.
+        let mut files: Vec<(String, u128)> = self.files
+            .lock()
+            .iter()
+            .filter(|(_, file)| file.accessed())
+            .map(|(id, file)| {
+                let package = id.package().map(|spec| spec.to_string()).unwrap_or_default();
+                let path = id.vpath().as_rootless_path().to_string_lossy();
+                (format!("{package}/{path}"), file.fingerprint())
+            })
+            .collect();
+        files.sort_unstable();
+
+        let entry_path = self.entry.id().vpath().as_rootless_path().to_string_lossy();
+        let entry = (entry_path, self.entry.text(), self.entry_wrapper.as_ref().map(|x| x.text()));
+        let output_configuration = (
+            format!("{:?}", self.pdf_standards),
+            &self.pdf_ident,
+            self.pdf_timestamp,
+            self.ppi.to_bits(),
+            self.background,
+            self.svg_background,
+            self.svg_responsive,
+            self.autocrop,
+            self.autocrop_padding,
+            &self.producer,
+            self.deny_warnings
+        );
+
+        return typst_utils::hash128(&(
+            entry,
+            files,
+            self.library_fingerprint,
+            fonts,
+            output_configuration
+        ));
+    }
+}
@@ -18,7 +18,8 @@
 //! -   ✅ **Capture compilation result**:
 //!     Typst projects are compiled in memory, compilation result is immediately available as a
 //!     collection of bytes. You don't have to invoke the [official CLI][typst-cli]. Compiler input
-//!     can be either **Content** (string) or **File** (entry filename and project root).
+//!     can be either **Content** (string, optionally with project root) or **File** (entry filename
+//!     and project root).
 //!
 //! -   🔃 **Custom data loading**:
 //!     Provides a way to override Typst standard library and add custom symbols to the global
@@ -28,7 +29,8 @@
 //!
 //! -   🔠 **Lazily loaded fonts**:
 //!     Fonts are loaded lazily on-demand. Font cache keeps all fonts metadata in memory, but the
-//!     actual font data is lazy loaded.
+//!     actual font data is lazy loaded. TTF, OTF, TTC and WOFF fonts are supported, WOFF2 fonts
+//!     are **not** (convert them to TTF/OTF or WOFF first).
 //!
 //! -   🗃️ **Font caching**:
 //!     Global font cache keeps track of all fonts. The cache is updated if new fonts are loaded
@@ -163,14 +165,6 @@
 //!
 //! # Notes / Warnings
 //!
-//! -   📁 **Filename restrictions**: Due to migration from typst 0.11 to 0.12, in order for this
-//!     library to function without major refactors, filenames/paths that contains text
-//!     **`"CUSTOM_SOURCE_CONTENT_INPUT_IN_MEMORY_FILE"` should not be used**. This specific name is
-//!     reserved, as it is used internally within this library, to denote that the compiler input
-//!     should not be retreived from the file, because it's content is directly available in memory
-//!     and should be passed to compiler (and then later read) as is. In very simple terms this
-//!     string is used to distinguish content input from file input.
-//!
 //! -   ⌚ **Synchronous**:
 //!     Every mutex in this library is sync `parking_lot::Mutex`.
 //!     Meaning, font caching and (opt-in) parallel PNG/SVG compilation and cache size calculation
@@ -190,15 +184,18 @@
 //!
 //! [typst-cli]: https://github.com/typst/typst/tree/main/crates/typst-cli
 
-pub(crate) const RESERVED_IN_MEMORY_IDENTIFIER: &str = "CUSTOM_SOURCE_CONTENT_INPUT_IN_MEMORY_FILE";
-
 mod builder;
 mod compiler;
+mod diagnostics;
 mod errors;
 mod files;
 mod fonts;
-mod package;
+mod inspection;
+pub mod package;
 mod parameters;
+mod pdf;
+mod raster;
+mod woff;
 
 /// Necessary re-exports for completeness. Typst errors, values, types, ...
 ///
@@ -208,24 +205,36 @@ pub mod reexports {
 
     pub use ecow::{EcoString, EcoVec};
 
-    pub use typst::layout::{Abs, Angle, Em, Length, Ratio, Rel};
+    pub use typst::layout::{Abs, Angle, Em, Frame, Length, Page, Ratio, Rel, Size};
+    pub use typst::model::Document;
     pub use typst_utils::{PicoStr, Scalar, Static};
 
-    pub use typst::diag::{PackageError, FileError, SourceDiagnostic};
+    pub use typst::diag::{PackageError, FileError, Severity, SourceDiagnostic};
     pub use typst::foundations::{
         Arg, Args, Array, Bytes, Content, Datetime, Dict, Duration, Dynamic, Func, IndexMap,
         IntoValue, Label, Module, NativeTypeData, Plugin, Str, Style, Styles, Type, Value, Version,
-        Element, NativeElement, NativeElementData, array, eco_vec
+        Element, NativeElement, NativeElementData, Scope, array, eco_vec
     };
 
     pub use typst::visualize::{
         Cmyk, Color, Gradient, Hsl, Hsv, LinearRgb, Luma, Oklab, Oklch, Pattern, Rgb
     };
-    pub use typst_syntax::Span;
+    pub use typst::{Library, LibraryBuilder};
+    pub use typst_pdf::PdfStandard;
+    pub use typst_syntax::{FileId, Span, VirtualPath};
+    pub use typst_syntax::package::PackageSpec;
+
+    pub use tiny_skia::Pixmap;
 }
 
 pub use builder::CompilerBuilder;
 pub use compiler::Compiler;
+pub use diagnostics::ResolvedDiagnostic;
+#[cfg(feature = "serde")]
+pub use diagnostics::SerializableDiagnostic;
 pub use errors::WrapperError;
 pub use fonts::FontCache;
-pub use parameters::{CompilerOutput, Input};
+pub use parameters::{
+    CompilePlan, CompiledBytes, CompilerOutput, ContentBounds, DatetimePrecision, FontCacheStats,
+    FontEntry, Input, MultiOutput, OutlineNode, OutputFormat, SvgPage
+};
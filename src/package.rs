@@ -1,21 +1,84 @@
-//! Provides a way to [create a http agent](create_http_agent) and
-//! [download typst packages from the repository](prepare_package).
+//! Provides a way to create a http agent and download typst packages from the repository.
 //!
-//! ### Used internally.
+//! Only listing package [dependencies] is public, everything else is used internally.
 
+use std::collections::HashMap;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use typst::diag::{eco_format, PackageError, PackageResult};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use ecow::EcoString;
+use sha2::{Digest, Sha256};
+use typst::diag::{eco_format, PackageError};
+use typst_syntax::ast;
 use typst_syntax::package::PackageSpec;
+use typst_syntax::{SyntaxKind, SyntaxNode};
+
+use crate::errors::{WrapperError, WrapperResult};
 
 /// `typst-lib-wrapper` user agent, used when downloading a package.
 const USER_AGENT: &str = concat!("typst-lib-wrapper/", env!("CARGO_PKG_VERSION"));
 
-/// Typst package repository location.
+/// Typst package repository location, used for the `@preview` namespace.
 const HOST: &str = "https://packages.typst.org";
 
+/// Package download progress callback, called with the number of bytes read so far
+/// and the total size (from `Content-Length` header) if known.
+#[derive(Clone)]
+pub(crate) struct DownloadProgress(pub(crate) Arc<dyn Fn(u64, Option<u64>) + Send + Sync>);
+
+impl std::fmt::Debug for DownloadProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DownloadProgress")
+    }
+}
+
+/// Settings used when resolving and downloading packages.
+#[derive(Debug, Clone)]
+pub(crate) struct PackageSettings {
+    /// HTTP client used to download packages.
+    pub(crate) http_client: ureq::Agent,
+    /// Maximum total size of extracted package files in bytes. Unlimited if `None`.
+    pub(crate) max_extracted_size: Option<u64>,
+    /// Point in time after which downloads are aborted. No deadline if `None`.
+    pub(crate) deadline: Option<Instant>,
+    /// Package registry hosts keyed by namespace, `@preview` uses the typst repository
+    /// unless it's overridden.
+    pub(crate) registries: HashMap<String, String>,
+    /// Packages are never downloaded, only local packages are used.
+    pub(crate) offline: bool,
+    /// Directory packages are looked up in and downloaded to, instead of the OS default
+    /// data and cache directories.
+    pub(crate) cache_dir: Option<PathBuf>,
+    /// Called while the package archive is being downloaded.
+    pub(crate) progress: Option<DownloadProgress>,
+    /// Expected SHA-256 checksums (hex) of downloaded package archives.
+    pub(crate) checksums: HashMap<PackageSpec, String>,
+    /// Number of retries of a failed download, `0` disables retrying.
+    pub(crate) retries: u32,
+    /// Wait before the first retry, doubled after each retry.
+    pub(crate) retry_backoff: Duration
+}
+
+impl PackageSettings {
+    /// Resolves the registry host for the package `namespace`.
+    /// Returns `None` if packages in the `namespace` can't be downloaded.
+    fn registry_host(&self, namespace: &str) -> Option<&str> {
+        if let Some(host) = self.registries.get(namespace) {
+            return Some(host.trim_end_matches('/'));
+        }
+
+        return (namespace == "preview").then_some(HOST);
+    }
+}
+
 /// Creates HTTP `ureq::Agent`.
+///
+/// Provided `agent` is returned as is, `timeout` is applied only to the new agent.
 pub(crate) fn create_http_agent(
-    agent: Option<ureq::Agent>
+    agent: Option<ureq::Agent>,
+    timeout: Option<Duration>
 ) -> ureq::Agent {
     // Returns provided agent.
     if let Some(http_agent) = agent {
@@ -27,6 +90,11 @@ pub(crate) fn create_http_agent(
         // Set user agent.
         builder = builder.user_agent(USER_AGENT);
 
+        // Set timeout of the whole request (connecting, sending and reading).
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+
         return builder.build();
     }
 }
@@ -34,80 +102,326 @@ pub(crate) fn create_http_agent(
 /// Tries to resolve package specification (`spec`) to [PathBuf].
 ///
 /// If the package is not available locally then it'll try to download it from the repository
-/// using `http_client`. It makes packages available in the on-disk cache.
+/// using [PackageSettings]. It makes packages available in the on-disk cache.
+///
+/// Packages are looked up in `{data_dir}/typst/packages` and `{cache_dir}/typst/packages`
+/// (OS default directories) or only in the [custom cache directory](PackageSettings::cache_dir)
+/// if it's configured. Package directory layout is `{namespace}/{name}/{version}`.
 pub(crate) fn prepare_package(
     spec: &PackageSpec,
-    http_client: &ureq::Agent
-) -> PackageResult<PathBuf> {
-    let subdir = format!("typst/packages/{}/{}/{}", spec.namespace, spec.name, spec.version);
+    settings: &PackageSettings
+) -> WrapperResult<PathBuf> {
+    let subdir = format!("{}/{}/{}", spec.namespace, spec.name, spec.version);
 
-    // Check `data_dir` first.
-    if let Some(data_dir) = dirs::data_dir() {
-        let dir = data_dir.join(&subdir);
-        if dir.exists() {
-            return Ok(dir);
+    let cache_dir: Option<PathBuf> = match &settings.cache_dir {
+        // Custom cache directory replaces both OS default directories.
+        Some(cache_dir) => Some(cache_dir.clone()),
+        None => {
+            // Check `data_dir` first.
+            if let Some(data_dir) = dirs::data_dir() {
+                let dir = data_dir.join("typst/packages").join(&subdir);
+                if dir.exists() {
+                    return Ok(dir);
+                }
+            }
+
+            dirs::cache_dir().map(|cache_dir| cache_dir.join("typst/packages"))
         }
-    }
+    };
 
     // Check `cache_dir` and download package if necessary.
-    if let Some(cache_dir) = dirs::cache_dir() {
+    if let Some(cache_dir) = cache_dir {
         let dir = cache_dir.join(&subdir);
         if dir.exists() {
             return Ok(dir);
         }
 
-        // Download from network if it doesn't exist yet.
-        // The `@preview` namespace is the only namespace that supports on-demand fetching.
-        if spec.namespace == "preview" {
-            download_package(spec, &dir, http_client)?;
+        // Download from network if it doesn't exist yet (and network is allowed).
+        // The `@preview` namespace and namespaces with configured registry support
+        // on-demand fetching.
+        if settings.offline {
+            return Err(PackageError::NotFound(spec.clone()).into());
+        }
+        if let Some(host) = settings.registry_host(&spec.namespace) {
+            download_package(spec, host, &dir, settings)?;
             if dir.exists() {
                 return Ok(dir);
             }
         }
     }
 
-    return Err(PackageError::NotFound(spec.clone()));
+    return Err(PackageError::NotFound(spec.clone()).into());
+}
+
+/// Lists typst packages the package with specification `spec` depends on (directly).
+///
+/// The package is resolved the same way as during compilation (downloaded with `http_client`
+/// if it's not available locally). Typst package manifests (`typst.toml`) don't declare
+/// dependencies, so they are collected from the `#import` and `#include` statements
+/// of all package source files. Returned specifications are unique and sorted.
+///
+/// Call it again for each returned dependency to build a full transitive list, for example
+/// to prefetch all packages for an air-gapped deployment.
+///
+/// # Example
+/// ```
+/// use std::str::FromStr;
+/// use typst_lib_wrapper::reexports::PackageSpec;
+///
+/// let agent = ureq::AgentBuilder::new().build();
+/// let mut pending = vec![PackageSpec::from_str("@preview/cetz:0.3.1").expect("Invalid spec")];
+/// let mut resolved: Vec<PackageSpec> = Vec::new();
+///
+/// while let Some(spec) = pending.pop() {
+///     if resolved.contains(&spec) { continue; }
+///     let dependencies = typst_lib_wrapper::package::dependencies(&spec, &agent)
+///         .expect("Couldn't resolve the package");
+///     pending.extend(dependencies);
+///     resolved.push(spec);
+/// }
+/// ```
+pub fn dependencies(
+    spec: &PackageSpec,
+    http_client: &ureq::Agent
+) -> WrapperResult<Vec<PackageSpec>> {
+    let settings = PackageSettings {
+        http_client: http_client.clone(),
+        max_extracted_size: None,
+        deadline: None,
+        registries: HashMap::new(),
+        offline: false,
+        cache_dir: None,
+        progress: None,
+        checksums: HashMap::new(),
+        retries: 0,
+        retry_backoff: Duration::ZERO
+    };
+    let package_dir: PathBuf = prepare_package(spec, &settings)?;
+
+    let mut dependencies: Vec<PackageSpec> = Vec::new();
+    let mut pending_dirs: Vec<PathBuf> = vec![package_dir];
+    while let Some(dir) = pending_dirs.pop() {
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|err| PackageError::Other(Some(eco_format!("{err}"))))?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending_dirs.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "typ") {
+                // Unreadable (non UTF-8) files can't be imported, skip them.
+                if let Ok(text) = std::fs::read_to_string(&path) {
+                    collect_imports(&typst_syntax::parse(&text), &mut dependencies);
+                }
+            }
+        }
+    }
+
+    dependencies.retain(|dependency| dependency != spec);
+    dependencies.sort_by_key(|dependency| dependency.to_string());
+    dependencies.dedup();
+
+    return Ok(dependencies);
+}
+
+/// Recursively collects package specifications imported or included in the syntax `node`.
+fn collect_imports(node: &SyntaxNode, dependencies: &mut Vec<PackageSpec>) {
+    let source = match node.kind() {
+        SyntaxKind::ModuleImport => node.cast::<ast::ModuleImport>().map(|x| x.source()),
+        SyntaxKind::ModuleInclude => node.cast::<ast::ModuleInclude>().map(|x| x.source()),
+        _ => None
+    };
+
+    if let Some(ast::Expr::Str(path)) = source {
+        if let Ok(spec) = PackageSpec::from_str(&path.get()) {
+            dependencies.push(spec);
+        }
+    }
+
+    for child in node.children() {
+        collect_imports(child, dependencies);
+    }
 }
 
-/// Downloads a typst package with specification `spec` from the repository using `http_client`,
-/// decompresses and saves it to the `package_dir`.
+/// Downloads a typst package with specification `spec` from the registry `host` using
+/// [PackageSettings], decompresses and saves it to the `package_dir`.
+///
+/// Package archive is expected at `{host}/{namespace}/{name}-{version}.tar.gz`.
+///
+/// Extraction is aborted (and `package_dir` deleted) if the total size of extracted files
+/// exceeds [max_extracted_size](PackageSettings::max_extracted_size).
+///
+/// Download is aborted if the [deadline](PackageSettings::deadline) is exceeded.
+/// [Progress](PackageSettings::progress) is reported after each read chunk.
+///
+/// Transient failures (connection errors, timeouts, `429` and `5xx` responses) are retried
+/// up to [retries](PackageSettings::retries) times, waiting [retry_backoff](
+/// PackageSettings::retry_backoff) before the first retry and doubling it after each one.
+/// Missing package (`404`) is never retried.
+///
+/// If a [checksum](PackageSettings::checksums) is configured for the package, the downloaded
+/// archive is verified before extraction, mismatch returns
+/// [WrapperError::PackageChecksumMismatch].
 fn download_package(
     spec: &PackageSpec,
+    host: &str,
     package_dir: &Path,
-    http_client: &ureq::Agent
-) -> PackageResult<()> {
+    settings: &PackageSettings
+) -> WrapperResult<()> {
+    let url = format!("{host}/{}/{}-{}.tar.gz", spec.namespace, spec.name, spec.version);
+
+    let mut attempt: u32 = 0;
+    let buffer: Vec<u8> = loop {
+        let message: String = match fetch_archive(&url, settings) {
+            Ok(buffer) => break buffer,
+            Err(FetchError::NotFound) => return Err(PackageError::NotFound(spec.clone()).into()),
+            Err(FetchError::Retryable(message)) if attempt < settings.retries => message,
+            Err(FetchError::Retryable(message) | FetchError::Fatal(message)) => {
+                // Network errors carry the package and the URL, so failures can be triaged.
+                return Err(WrapperError::PackageDownloadFailed(spec.to_string(), url, message));
+            }
+        };
+
+        // Doesn't wait for a retry that would start after the deadline.
+        let backoff = settings.retry_backoff.saturating_mul(2u32.saturating_pow(attempt));
+        if settings.deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline) {
+            return Err(WrapperError::PackageDownloadFailed(spec.to_string(), url, message));
+        }
+        std::thread::sleep(backoff);
+        attempt += 1;
+    };
+
+    // Verifies the archive before extracting anything.
+    if let Some(expected) = settings.checksums.get(spec) {
+        let actual: String = Sha256::digest(&buffer)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(WrapperError::PackageChecksumMismatch(
+                spec.to_string(),
+                expected.trim().to_lowercase(),
+                actual
+            ));
+        }
+    }
+
+    let decompressed = flate2::read::GzDecoder::new(buffer.as_slice());
+
+    unpack_archive(tar::Archive::new(decompressed), package_dir, settings.max_extracted_size)
+        .map_err(|err| {
+            std::fs::remove_dir_all(package_dir).ok(); // Delete malformed (or too big) archive.
+            PackageError::MalformedArchive(Some(err))
+        })?;
+
+    return Ok(());
+}
 
-    // Build url and send request.
-    let url = format!("{HOST}/preview/{}-{}.tar.gz", spec.name, spec.version);
-    let response: ureq::Response = match http_client.get(&url).call() {
+/// Failed package archive download.
+enum FetchError {
+    /// Package archive doesn't exist (`404`).
+    NotFound,
+    /// Transient failure, the download can be retried.
+    Retryable(String),
+    /// Permanent failure.
+    Fatal(String)
+}
+
+/// Downloads the package archive from `url` into memory, limited by the time left until
+/// the [deadline](PackageSettings::deadline). Reports [progress](PackageSettings::progress)
+/// after each read chunk.
+fn fetch_archive(url: &str, settings: &PackageSettings) -> Result<Vec<u8>, FetchError> {
+    let deadline_exceeded = || FetchError::Fatal(String::from("request deadline exceeded"));
+
+    let mut request = settings.http_client.get(url);
+    if let Some(deadline) = settings.deadline {
+        let remaining = deadline
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+            .ok_or_else(deadline_exceeded)?;
+        request = request.timeout(remaining);
+    }
+
+    let response: ureq::Response = match request.call() {
         Ok(resp) => resp,
-        Err(ureq::Error::Status(404, _)) =>
-            return Err(PackageError::NotFound(spec.clone())),
+        Err(ureq::Error::Status(404, _)) => return Err(FetchError::NotFound),
         Err(err) => {
-            let message = eco_format!("{err}");
-            return Err(PackageError::NetworkFailed(Some(message)));
+            let retryable = match &err {
+                ureq::Error::Status(code, _) => *code == 429 || *code >= 500,
+                ureq::Error::Transport(_) => true
+            };
+            let message = http_error_message(err, url);
+            return Err(match retryable {
+                true => FetchError::Retryable(message),
+                false => FetchError::Fatal(message)
+            });
         }
     };
 
     // Try to get buffer size from `Content-Length` header.
     // If not present/error use zero. `Vec::with_capacity` can handle zero.
-    let content_length: usize = match response.header("Content-Length") {
-        None => 0,
-        Some(header) => header.parse::<usize>().unwrap_or(0)
+    let content_length: Option<u64> = response
+        .header("Content-Length")
+        .and_then(|header| header.parse::<u64>().ok());
+    let mut buffer: Vec<u8> = Vec::with_capacity(content_length.unwrap_or(0) as usize);
+
+    // Try to read HTTP response to buffer in chunks, checking the deadline.
+    let mut reader = response.into_reader();
+    let mut chunk = [0u8; 16 * 1024];
+    loop {
+        if settings.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(deadline_exceeded());
+        }
+
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break, // End of the response.
+            Ok(read) => read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(FetchError::Retryable(err.to_string()))
+        };
+        buffer.extend_from_slice(&chunk[..read]);
+
+        if let Some(progress) = &settings.progress {
+            (progress.0)(buffer.len() as u64, content_length);
+        }
+    }
+
+    return Ok(buffer);
+}
+
+/// Describes the ureq `err` without the `url` prefix (it's already part of the download error).
+fn http_error_message(err: ureq::Error, url: &str) -> String {
+    let message = err.to_string();
+    return match message.strip_prefix(url).and_then(|rest| rest.strip_prefix(": ")) {
+        Some(rest) => rest.to_string(),
+        None => message
     };
-    let mut buffer: Vec<u8> = Vec::with_capacity(content_length);
+}
 
-    // Try to read HTTP response to buffer and decompress it.
-    response.into_reader().read_to_end(&mut buffer)
-        .map_err(|err| PackageError::NetworkFailed(Some(eco_format!("{err}"))))?;
+/// Unpacks the `archive` into `package_dir` entry by entry, keeping track of the total
+/// extracted size. Fails as soon as the total size exceeds `max_extracted_size`.
+fn unpack_archive<R: std::io::Read>(
+    mut archive: tar::Archive<R>,
+    package_dir: &Path,
+    max_extracted_size: Option<u64>
+) -> Result<(), EcoString> {
+    std::fs::create_dir_all(package_dir).map_err(|err| eco_format!("{err}"))?;
 
-    let decompressed = flate2::read::GzDecoder::new(buffer.as_slice());
+    let mut extracted_size: u64 = 0;
+    for entry in archive.entries().map_err(|err| eco_format!("{err}"))? {
+        let mut entry = entry.map_err(|err| eco_format!("{err}"))?;
 
-    tar::Archive::new(decompressed).unpack(package_dir)
-        .map_err(|err| {
-            std::fs::remove_dir_all(package_dir).ok(); // Delete malformed archive.
-            PackageError::MalformedArchive(Some(eco_format!("{err}")))
-        })?;
+        extracted_size = extracted_size.saturating_add(entry.size());
+        if let Some(max_size) = max_extracted_size {
+            if extracted_size > max_size {
+                return Err(eco_format!(
+                    "package exceeds the maximum extracted size of {max_size} bytes"
+                ));
+            }
+        }
+
+        entry.unpack_in(package_dir).map_err(|err| eco_format!("{err}"))?;
+    }
 
     return Ok(());
 }
@@ -1,9 +1,14 @@
 //! Contains some I/O parameters for the [Compiler](crate::compiler::Compiler).
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use ecow::EcoVec;
-use typst::diag::SourceDiagnostic;
+use typst::diag::{Severity, SourceDiagnostic};
+use typst_syntax::FileId;
+use typst_syntax::package::PackageSpec;
+
+use crate::errors::WrapperError;
 
 /// Typst input content/file.
 ///
@@ -26,6 +31,24 @@ use typst::diag::SourceDiagnostic;
 ///     .expect("Couldn't build the compiler");
 /// ```
 ///
+/// ## Content with root
+/// Same as content, but files (imports, images, data, ...) are resolved relative to
+/// the provided project `root` instead of the current directory.
+///
+/// # Example
+/// Creates content input that reads data from the project directory.
+/// ```
+/// let content = r##"
+///     #let data = json("data.json")
+///     = #data.title
+/// "##;
+/// let input = Input::content_with_root(content, "./project");
+///
+/// CompilerBuilder::with_input(input)
+///     .build()
+///     .expect("Couldn't build the compiler");
+/// ```
+///
 /// ## File
 /// Defined by two values:
 /// - `entry`: Main (entry) typst **filename**.
@@ -55,27 +78,23 @@ pub enum Input {
     },
 
     /// Creates typst input from [String].
-    Content(String)
-}
+    Content(String),
 
-impl Input {
-    /// Checks if the provided [Input] contains the reserved (forbidden) filename/path.
-    pub(crate) fn is_forbidden(&self) -> bool {
-        match self {
-            Self::Content(_) => false,
-            Self::File { entry, root } => {
-                if entry.contains(crate::RESERVED_IN_MEMORY_IDENTIFIER) {
-                    return true;
-                }
-
-                root
-                    .to_str()
-                    .map(|x| x.contains(crate::RESERVED_IN_MEMORY_IDENTIFIER))
-                    .unwrap_or(false)
-            }
-        }
+    /// Creates typst input from [String], other files are resolved relative to the `root`.
+    ///
+    /// The main source is **always** the in-memory `content`, even if there is a file with
+    /// the same name on disk. Every other file is resolved from the virtual files (see
+    /// [add_virtual_file](crate::builder::CompilerBuilder::add_virtual_file)) first,
+    /// then from the `root` (and additional roots).
+    ContentWithRoot {
+        /// Main typst source.
+        content: String,
+        /// Typst project root used to resolve all other files.
+        root: PathBuf
     }
+}
 
+impl Input {
     /// Creates [Input] variant [Input::Content] from anything convertable to [String].
     ///
     /// # Example
@@ -98,6 +117,9 @@ impl Input {
     /// - `root` is the path to the project root. It's used to resolve all relative paths,
     /// including the entry file.
     ///
+    /// Both can be (or go through) symbolic links. Entry reached through a symbolic link
+    /// within the `root` is accepted, even if the link points outside of the `root`.
+    ///
     /// # Example
     /// ```
     /// let entry = "main.typ";
@@ -107,6 +129,19 @@ impl Input {
     pub fn file(entry: impl ToString, root: impl Into<PathBuf>) -> Self {
         Self::File { entry: entry.to_string(), root: root.into() }
     }
+
+    /// Creates [Input] variant [Input::ContentWithRoot] from anything convertable to [String]
+    /// for `content` and anything convertable [Into] [PathBuf] for `root`.
+    ///
+    /// # Example
+    /// ```
+    /// let content = "#image(\"logo.png\")";
+    /// let root = "./project";
+    /// let input = Input::content_with_root(content, root);
+    /// ```
+    pub fn content_with_root(content: impl ToString, root: impl Into<PathBuf>) -> Self {
+        Self::ContentWithRoot { content: content.to_string(), root: root.into() }
+    }
 }
 
 /// Output from the typst compiler. Consists of:
@@ -153,3 +188,354 @@ pub struct CompilerOutput<T> {
     /// Compilation errors.
     pub errors: EcoVec<SourceDiagnostic>
 }
+
+impl<T> CompilerOutput<T> {
+    /// Checks if typst reported that the layout did not converge.
+    ///
+    /// Typst re-layouts the document until all introspections (references, counters, outlines,
+    /// queries, ...) stabilize. The number of attempts is fixed by typst (5 in typst 0.12) and
+    /// **can't be configured**. If the layout doesn't stabilize, typst only emits a warning
+    /// and the output may contain wrong page numbers or references.
+    ///
+    /// Returns [WrapperError::LayoutDidNotConverge] with a hint if such warning (or error)
+    /// is present, otherwise `Ok`.
+    ///
+    /// # Example
+    /// Rejects documents with unstable layout.
+    /// ```
+    /// let entry = "report.typ";
+    /// let root = "./reports";
+    ///
+    /// let compiled = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler")
+    ///     .compile_pdf();
+    ///
+    /// if let Err(err) = compiled.ensure_layout_converged() {
+    ///     eprintln!("{err}");
+    /// }
+    /// ```
+    pub fn ensure_layout_converged(&self) -> Result<(), WrapperError> {
+        let diagnostic = self.errors
+            .iter()
+            .chain(self.warnings.iter())
+            .find(|diagnostic| diagnostic.message.starts_with("layout did not converge"));
+
+        return match diagnostic {
+            Some(diagnostic) => {
+                Err(WrapperError::LayoutDidNotConverge(diagnostic.message.to_string()))
+            },
+            None => Ok(())
+        };
+    }
+
+    /// Returns the number of warnings.
+    ///
+    /// # Example
+    /// ```
+    /// let compiled = CompilerBuilder::with_input(Input::content("= Hello"))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler")
+    ///     .compile_pdf();
+    ///
+    /// assert_eq!(compiled.warning_count(), 0);
+    /// ```
+    pub fn warning_count(&self) -> usize {
+        return self.warnings.len();
+    }
+
+    /// Returns the number of errors.
+    ///
+    /// # Example
+    /// ```
+    /// let compiled = CompilerBuilder::with_input(Input::content("#panic()"))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler")
+    ///     .compile_pdf();
+    ///
+    /// assert_eq!(compiled.error_count(), 1);
+    /// ```
+    pub fn error_count(&self) -> usize {
+        return self.errors.len();
+    }
+
+    /// Checks if there are any errors. The `output` is usually `None` if there are.
+    ///
+    /// # Example
+    /// ```
+    /// let compiled = CompilerBuilder::with_input(Input::content("#panic()"))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler")
+    ///     .compile_pdf();
+    ///
+    /// assert!(compiled.has_errors());
+    /// assert!(compiled.output.is_none());
+    /// ```
+    pub fn has_errors(&self) -> bool {
+        return !self.errors.is_empty();
+    }
+
+    /// Counts all diagnostics (warnings and errors) grouped by their [Severity].
+    /// Severities without any diagnostic are omitted.
+    ///
+    /// # Example
+    /// Logs the summary of the compilation.
+    /// ```
+    /// let compiled = CompilerBuilder::with_input(Input::content("#panic()"))
+    ///     .build()
+    ///     .expect("Couldn't build the compiler")
+    ///     .compile_pdf();
+    ///
+    /// let breakdown = compiled.severity_breakdown();
+    /// assert_eq!(breakdown.get(&Severity::Error), Some(&1));
+    /// assert_eq!(breakdown.get(&Severity::Warning), None);
+    /// ```
+    pub fn severity_breakdown(&self) -> HashMap<Severity, usize> {
+        let mut breakdown: HashMap<Severity, usize> = HashMap::new();
+        for diagnostic in self.errors.iter().chain(self.warnings.iter()) {
+            *breakdown.entry(diagnostic.severity).or_insert(0) += 1;
+        }
+
+        return breakdown;
+    }
+}
+
+/// Bounding box `(min_x, min_y, max_x, max_y)` in points, measured from the top-left corner
+/// of the page. Returned by [content_bounds](crate::compiler::Compiler::content_bounds).
+pub type ContentBounds = (f64, f64, f64, f64);
+
+/// Node of the document outline tree, created by
+/// [outline_tree](crate::compiler::Compiler::outline_tree).
+///
+/// Every node is an outlined heading, except the root node which represents the whole document.
+/// Root node has `level` 0, `page` 1 and the document title (empty if not set) as `title`.
+///
+/// With an opt-in feature (`"serde"`) it implements `serde::Serialize`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OutlineNode {
+    /// Plain text of the heading.
+    pub title: String,
+    /// Heading level, **starting from 1**.
+    pub level: usize,
+    /// Page number (physical, not the displayed one) of the heading, **starting from 1**.
+    pub page: usize,
+    /// Nested headings.
+    pub children: Vec<OutlineNode>
+}
+
+/// Everything a compilation needs, returned by [plan](crate::compiler::Compiler::plan).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilePlan {
+    /// Number of pages of the compiled document.
+    pub page_count: usize,
+    /// Packages required by the document, sorted.
+    pub packages: Vec<PackageSpec>,
+    /// Families of the fonts used in the document, sorted.
+    pub fonts: Vec<String>,
+    /// Font families requested by the document that aren't available (lowercase), sorted.
+    pub missing_fonts: Vec<String>,
+    /// Files accessed during compilation, starting with the entry, the rest sorted by path.
+    pub files: Vec<FileId>
+}
+
+/// Statistics of the [FontCache](crate::fonts::FontCache), returned by
+/// [stats](crate::fonts::FontCache::stats).
+///
+/// With an opt-in feature (`"serde"`) it implements `serde::Serialize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FontCacheStats {
+    /// Number of all fonts (faces) in the cache.
+    pub total: usize,
+    /// Number of fonts whose data is currently loaded in memory.
+    pub loaded: usize,
+    /// Number of typst embedded fonts (see `embed_typst_fonts` feature).
+    pub embedded: usize,
+    /// Size of all loaded font data in **bytes**, including embedded fonts.
+    pub loaded_bytes: usize
+}
+
+/// Compiled SVG page with its dimensions, returned by
+/// [compile_svg_with_dimensions](crate::compiler::Compiler::compile_svg_with_dimensions).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgPage {
+    /// SVG bytes.
+    pub svg: Vec<u8>,
+    /// Page width in points.
+    pub width_pt: f64,
+    /// Page height in points.
+    pub height_pt: f64
+}
+
+/// Font available in the [FontCache](crate::fonts::FontCache), returned by
+/// [list_fonts](crate::fonts::FontCache::list_fonts).
+///
+/// With an opt-in feature (`"serde"`) it implements `serde::Serialize`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FontEntry {
+    /// Font family name, as used in `#set text(font: ...)`.
+    pub family: String,
+    /// Font style, one of `"normal"`, `"italic"` or `"oblique"`.
+    pub style: String,
+    /// Font weight, from 100 (thin) to 900 (black), 400 is regular.
+    pub weight: u16,
+    /// Whether the font is typst embedded font (see `embed_typst_fonts` feature).
+    pub embedded: bool
+}
+
+/// Output format of the compiled document, used by [compile](crate::compiler::Compiler::compile)
+/// and [compile_all](crate::compiler::Compiler::compile_all).
+///
+/// Typst 0.12 can only export paged documents, so there's no HTML format (yet).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum OutputFormat {
+    /// PDF, see [compile_pdf](crate::compiler::Compiler::compile_pdf).
+    Pdf,
+    /// PNG, see [compile_png](crate::compiler::Compiler::compile_png).
+    Png,
+    /// SVG, see [compile_svg](crate::compiler::Compiler::compile_svg).
+    Svg
+}
+
+/// Compiled bytes, returned by [compile](crate::compiler::Compiler::compile).
+/// Variant depends on the requested [OutputFormat].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompiledBytes {
+    /// Whole document in a single file, used for [OutputFormat::Pdf].
+    Document(Vec<u8>),
+    /// One item for each page, used for [OutputFormat::Png] and [OutputFormat::Svg].
+    Pages(Vec<Vec<u8>>)
+}
+
+/// Precision of `datetime.today()`, see
+/// [with_datetime_precision](crate::builder::CompilerBuilder::with_datetime_precision).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DatetimePrecision {
+    /// Only the date (year, month, day), the default.
+    #[default]
+    DateOnly,
+    /// Date and time (hour, minute, second).
+    DateTime
+}
+
+/// Output of [compile_all](crate::compiler::Compiler::compile_all). Consists of:
+/// - `pdf`, `png`, `svg`: Optional output for each format, `None` if the format wasn't
+/// requested, or if the compilation (or encoding to that format) failed.
+/// - `warnings`: Compiler and export warnings, shared by all formats.
+/// - `errors`: Compiler and export errors, shared by all formats.
+#[derive(Debug, Default)]
+pub struct MultiOutput {
+    /// PDF bytes.
+    pub pdf: Option<Vec<u8>>,
+    /// PNG bytes, one item for each page.
+    pub png: Option<Vec<Vec<u8>>>,
+    /// SVG bytes, one item for each page.
+    pub svg: Option<Vec<Vec<u8>>>,
+    /// Warnings during compilation and export.
+    pub warnings: EcoVec<SourceDiagnostic>,
+    /// Compilation and export errors.
+    pub errors: EcoVec<SourceDiagnostic>
+}
+
+#[cfg(feature = "pdf_validation")]
+impl CompilerOutput<Vec<u8>> {
+    /// Structural self-check of the compiled PDF. Requires `pdf_validation` feature.
+    ///
+    /// Parses the PDF `output` with `lopdf` and checks that:
+    /// - every in-use cross-reference (xref) entry points to a loaded object,
+    /// - page count declared by the page tree matches the number of reachable pages,
+    /// - number of pages matches `expected_pages`, the page count of the document
+    /// (see [page_count](crate::compiler::Compiler::page_count)).
+    ///
+    /// Returns `Err` with a description of the first problem found, or if there is
+    /// no `output` to validate.
+    ///
+    /// # Note
+    /// This is a sanity check, not a full PDF (or PDF/A) conformance validation.
+    ///
+    /// # Example
+    /// Compiles document to PDF and validates it before writing it to the disk.
+    /// ```
+    /// let entry = "main.typ";
+    /// let root = "./project";
+    ///
+    /// let compiler = CompilerBuilder::with_file_input(entry, root)
+    ///     .build()
+    ///     .expect("Couldn't build the compiler");
+    ///
+    /// let pages = compiler.page_count().output.expect("Compilation failed");
+    /// let compiled = compiler.compile_pdf();
+    ///
+    /// compiled.validate_pdf(pages).expect("Invalid PDF");
+    /// ```
+    pub fn validate_pdf(&self, expected_pages: usize) -> Result<(), String> {
+        let bytes: &Vec<u8> = match &self.output {
+            Some(bytes) => bytes,
+            None => return Err(String::from("No PDF output to validate"))
+        };
+
+        let document = lopdf::Document::load_mem(bytes)
+            .map_err(|err| format!("Couldn't parse PDF: {err}"))?;
+
+        // Every in-use xref entry must resolve to a loaded object.
+        for (id, entry) in document.reference_table.entries.iter() {
+            if let lopdf::xref::XrefEntry::Normal { generation, .. } = entry {
+                if !document.objects.contains_key(&(*id, *generation)) {
+                    return Err(format!("Broken xref entry for object {id} {generation}"));
+                }
+            }
+        }
+
+        // Page count declared by the root of the page tree.
+        let declared_pages: i64 = document
+            .catalog()
+            .and_then(|catalog| catalog.get(b"Pages"))
+            .and_then(|pages| pages.as_reference())
+            .and_then(|pages_id| document.get_dictionary(pages_id))
+            .and_then(|pages| pages.get(b"Count"))
+            .and_then(|count| count.as_i64())
+            .map_err(|err| format!("Couldn't read page tree: {err}"))?;
+
+        let found_pages = document.get_pages().len();
+        if usize::try_from(declared_pages).ok() != Some(found_pages) {
+            return Err(format!(
+                "Page tree declares {declared_pages} pages, but {found_pages} were found"
+            ));
+        }
+        if found_pages != expected_pages {
+            return Err(format!(
+                "PDF has {found_pages} pages, but the document has {expected_pages}"
+            ));
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(all(test, feature = "pdf_validation"))]
+mod tests {
+    use crate::builder::CompilerBuilder;
+
+    #[test]
+    fn validates_pdf_page_count_and_structure() {
+        let compiler = CompilerBuilder::with_content_input("A #pagebreak() B #pagebreak() C")
+            .build()
+            .expect("Couldn't build the compiler");
+        let pages = compiler.page_count().output.expect("Compilation failed");
+        let mut compiled = compiler.compile_pdf();
+
+        assert_eq!(pages, 3);
+        assert_eq!(compiled.validate_pdf(pages), Ok(()));
+        assert!(compiled.validate_pdf(pages - 1).is_err());
+
+        let pdf = compiled.output.as_mut().expect("Missing PDF");
+        pdf.truncate(pdf.len() / 2);
+        assert!(compiled.validate_pdf(pages).is_err());
+    }
+}
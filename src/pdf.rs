@@ -0,0 +1,520 @@
+//! Provides a way to post-process compiled PDF bytes.
+//!
+//! Small in-place edits (like [setting the producer](set_producer)) patch the bytes
+//! and the cross-reference table directly. Merging PDFs (`pdf_merge` feature) uses `lopdf`.
+//!
+//! ### Used internally.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[cfg(feature = "pdf_merge")]
+use lopdf::{dictionary, Document, Object, ObjectId};
+
+/// Resolves the root of the page tree (`/Pages` entry of the catalog).
+#[cfg(feature = "pdf_merge")]
+fn pages_root_id(document: &Document) -> lopdf::Result<ObjectId> {
+    document.catalog()?.get(b"Pages")?.as_reference()
+}
+
+/// Appends a top-level bookmark (outline item) with `title` pointing to `page`.
+///
+/// Creates the document outline if it doesn't exist yet.
+#[cfg(feature = "pdf_merge")]
+fn push_bookmark(document: &mut Document, title: String, page: ObjectId) -> lopdf::Result<()> {
+    let outlines_id: ObjectId = match document.catalog()?.get(b"Outlines") {
+        Ok(outlines) => outlines.as_reference()?,
+        Err(_) => {
+            let id = document.add_object(dictionary! { "Type" => "Outlines", "Count" => 0 });
+            document.catalog_mut()?.set("Outlines", Object::Reference(id));
+            id
+        }
+    };
+
+    let outlines = document.get_dictionary(outlines_id)?;
+    let last: Option<ObjectId> = outlines.get(b"Last").and_then(Object::as_reference).ok();
+    let count: i64 = outlines.get(b"Count").and_then(Object::as_i64).unwrap_or(0);
+
+    let mut item = dictionary! {
+        "Title" => Object::string_literal(title),
+        "Parent" => Object::Reference(outlines_id),
+        "Dest" => vec![Object::Reference(page), "Fit".into()]
+    };
+    if let Some(last_id) = last {
+        item.set("Prev", Object::Reference(last_id));
+    }
+    let item_id = document.add_object(item);
+
+    // Links the item after the current last item.
+    if let Some(last_id) = last {
+        document.get_dictionary_mut(last_id)?.set("Next", Object::Reference(item_id));
+    }
+
+    let outlines = document.get_dictionary_mut(outlines_id)?;
+    if last.is_none() {
+        outlines.set("First", Object::Reference(item_id));
+    }
+    outlines.set("Last", Object::Reference(item_id));
+    outlines.set("Count", count.max(0) + 1);
+
+    Ok(())
+}
+
+/// Appends `appendices` PDFs after the pages of the `base` PDF and returns merged PDF bytes.
+///
+/// Page tree of each appendix is attached as a child of the `base` page tree (so inherited
+/// page attributes are kept) and page count is adjusted. Bookmarks of the `base` are kept
+/// and a top-level bookmark `"Appendix N"` is added for the first page of each appendix.
+/// Bookmarks of the appendices themselves are not merged.
+///
+/// Returns an error message if any of the PDFs couldn't be parsed or merged.
+#[cfg(feature = "pdf_merge")]
+pub(crate) fn append_pdfs(base: &[u8], appendices: &[Vec<u8>]) -> Result<Vec<u8>, String> {
+    let mut document = Document::load_mem(base)
+        .map_err(|err| format!("Couldn't parse PDF: {err}"))?;
+    let pages_id = pages_root_id(&document)
+        .map_err(|err| format!("Couldn't read page tree: {err}"))?;
+
+    for (index, appendix_bytes) in appendices.iter().enumerate() {
+        let number = index + 1;
+        let appendix_error =
+            |err: lopdf::Error| format!("Couldn't append appendix {number}: {err}");
+
+        let mut appendix = Document::load_mem(appendix_bytes).map_err(appendix_error)?;
+
+        // Makes object ids unique across both documents.
+        appendix.renumber_objects_with(document.max_id + 1);
+        document.max_id = appendix.max_id;
+
+        let appendix_catalog_id = appendix.trailer.get(b"Root")
+            .and_then(Object::as_reference)
+            .map_err(appendix_error)?;
+        let appendix_pages_id = pages_root_id(&appendix).map_err(appendix_error)?;
+        let appendix_pages_count = appendix.get_pages().len() as i64;
+        let appendix_first_page = appendix.page_iter().next();
+
+        // Moves all objects, except the appendix catalog.
+        document.objects.extend(
+            appendix.objects.into_iter().filter(|(id, _)| *id != appendix_catalog_id)
+        );
+
+        // Attaches appendix page tree to the main page tree.
+        document.get_dictionary_mut(appendix_pages_id)
+            .map_err(appendix_error)?
+            .set("Parent", Object::Reference(pages_id));
+
+        let pages = document.get_dictionary_mut(pages_id).map_err(appendix_error)?;
+        pages.get_mut(b"Kids")
+            .and_then(Object::as_array_mut)
+            .map_err(appendix_error)?
+            .push(Object::Reference(appendix_pages_id));
+        let pages_count = pages.get(b"Count").and_then(Object::as_i64).map_err(appendix_error)?;
+        pages.set("Count", pages_count + appendix_pages_count);
+
+        if let Some(first_page) = appendix_first_page {
+            push_bookmark(&mut document, format!("Appendix {number}"), first_page)
+                .map_err(appendix_error)?;
+        }
+    }
+
+    let mut buffer: Vec<u8> = Vec::with_capacity(base.len());
+    document.save_to(&mut buffer).map_err(|err| format!("Couldn't write PDF: {err}"))?;
+
+    return Ok(buffer);
+}
+
+/// Finds the last occurrence of `needle` in `haystack`.
+fn rfind_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    return haystack.windows(needle.len()).rposition(|window| window == needle);
+}
+
+/// Finds the first occurrence of `needle` in `haystack`, starting at `from`.
+fn find_bytes(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    return haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| position + from);
+}
+
+/// Skips ASCII whitespace starting at `position`, returns the position after it.
+fn skip_whitespace(pdf: &[u8], position: usize) -> usize {
+    return position + pdf[position..].iter().take_while(|byte| byte.is_ascii_whitespace()).count();
+}
+
+/// Parses ASCII digits starting at `start`. Returns the number and the range of digits.
+fn parse_number(pdf: &[u8], start: usize) -> Option<(usize, Range<usize>)> {
+    let length = pdf.get(start..)?.iter().take_while(|byte| byte.is_ascii_digit()).count();
+    let digits = std::str::from_utf8(pdf.get(start..start + length)?).ok()?;
+    return Some((digits.parse().ok()?, start..start + length));
+}
+
+/// Replaces the `range` of `pdf` with `bytes` and fixes the byte offsets in the
+/// cross-reference table and `startxref` that point after the `range`.
+///
+/// Works with classic cross-reference tables (as written by `typst_pdf`),
+/// not with cross-reference streams.
+fn replace_range(pdf: &mut Vec<u8>, range: Range<usize>, bytes: &[u8]) -> Result<(), String> {
+    let delta = bytes.len() as i64 - range.len() as i64;
+    let shift = |offset: usize| -> usize {
+        if offset >= range.end { (offset as i64 + delta) as usize } else { offset }
+    };
+
+    let startxref = rfind_bytes(pdf, b"startxref").ok_or("Missing `startxref`")?;
+    let digits_start = skip_whitespace(pdf, startxref + b"startxref".len());
+    let (xref_offset, xref_digits) = parse_number(pdf, digits_start)
+        .ok_or("Invalid `startxref`")?;
+    if xref_offset < range.end {
+        return Err(String::from("Can't edit the cross-reference table"));
+    }
+
+    pdf.splice(range.clone(), bytes.iter().copied());
+
+    // Shifts offsets of in-use entries, entries have fixed width, so they're patched in place.
+    let xref = shift(xref_offset);
+    if pdf.get(xref..xref + 4) != Some(b"xref") {
+        return Err(String::from("Cross-reference stream isn't supported"));
+    }
+    let mut position = xref + 4;
+    loop {
+        position = skip_whitespace(pdf, position);
+        let Some((_, first)) = parse_number(pdf, position) else {
+            break; // Reached the `trailer`.
+        };
+        position = first.end + 1;
+        let (count, count_digits) = parse_number(pdf, position).ok_or("Invalid xref section")?;
+        position = skip_whitespace(pdf, count_digits.end);
+
+        for _ in 0..count {
+            let entry = pdf.get(position..position + 20).ok_or("Truncated xref entry")?;
+            if entry[17] == b'n' {
+                let (offset, _) = parse_number(pdf, position).ok_or("Invalid xref entry")?;
+                let shifted = format!("{:010}", shift(offset));
+                pdf.splice(position..position + 10, shifted.bytes());
+            }
+            position += 20;
+        }
+    }
+
+    let xref_digits = shift(xref_digits.start)..shift(xref_digits.start) + xref_digits.len();
+    pdf.splice(xref_digits, xref.to_string().bytes());
+
+    return Ok(());
+}
+
+/// Encodes `text` as a PDF string, literal if it's ASCII, UTF-16BE hex string otherwise.
+fn pdf_string(text: &str) -> String {
+    if text.is_ascii() {
+        let escaped = text.replace('\\', "\\\\").replace('(', "\\(").replace(')', "\\)");
+        return format!("({escaped})");
+    }
+
+    let mut hex = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        hex.push_str(&format!("{unit:04X}"));
+    }
+    hex.push('>');
+    return hex;
+}
+
+/// Whether the `byte` is a PDF delimiter (ends names, numbers and keywords).
+fn is_delimiter(byte: u8) -> bool {
+    return byte.is_ascii_whitespace() || b"()<>[]{}/%".contains(&byte);
+}
+
+/// Skips one PDF object (dictionary, array, string, name, number or keyword) starting
+/// at `position` (after whitespace). Returns the range of the object.
+///
+/// Strings are skipped as a whole, so their content is never mistaken for PDF syntax.
+fn skip_object(pdf: &[u8], position: usize) -> Option<Range<usize>> {
+    let start = skip_whitespace(pdf, position);
+    let mut end = start;
+
+    match (pdf.get(start)?, pdf.get(start + 1)) {
+        (b'<', Some(b'<')) => {
+            end = dictionary_entries(pdf, start)?.1 + 2;
+        },
+        (b'<', _) => {
+            end = find_bytes(pdf, b">", start)? + 1;
+        },
+        (b'[', _) => {
+            end = skip_whitespace(pdf, start + 1);
+            while *pdf.get(end)? != b']' {
+                end = skip_whitespace(pdf, skip_object(pdf, end)?.end);
+            }
+            end += 1;
+        },
+        (b'(', _) => {
+            // Literal strings can contain balanced parentheses and escapes.
+            let mut depth: usize = 0;
+            loop {
+                match pdf.get(end)? {
+                    b'\\' => { end += 1; },
+                    b'(' => { depth += 1; },
+                    b')' => { depth -= 1; },
+                    _ => {}
+                }
+                end += 1;
+                if depth == 0 { break; }
+            }
+        },
+        (b'/', _) => {
+            end += 1 + pdf.get(start + 1..)?.iter().take_while(|x| !is_delimiter(**x)).count();
+        },
+        _ => {
+            end += pdf.get(start..)?.iter().take_while(|x| !is_delimiter(**x)).count();
+            if end == start { return None; }
+        }
+    }
+
+    return Some(start..end);
+}
+
+/// Ranges of a dictionary key and its value.
+type DictionaryEntry = (Range<usize>, Range<usize>);
+
+/// Parses the dictionary starting at `start` (`<<`). Returns ranges of its keys
+/// and values and the position of the closing `>>`.
+fn dictionary_entries(pdf: &[u8], start: usize) -> Option<(Vec<DictionaryEntry>, usize)> {
+    if pdf.get(start..start + 2)? != b"<<" {
+        return None;
+    }
+
+    let mut entries: Vec<DictionaryEntry> = Vec::new();
+    let mut position = skip_whitespace(pdf, start + 2);
+    while pdf.get(position..position + 2)? != b">>" {
+        let key = skip_object(pdf, position)?;
+        let value = skip_object(pdf, key.end)?;
+        position = skip_whitespace(pdf, value.end);
+        entries.push((key, value));
+    }
+
+    return Some((entries, position));
+}
+
+/// Finds the value of the `key` (name with the slash) in the dictionary entries.
+fn dictionary_value(pdf: &[u8], entries: &[DictionaryEntry], key: &[u8]) -> Option<Range<usize>> {
+    return entries
+        .iter()
+        .find(|(name, _)| &pdf[name.clone()] == key)
+        .map(|(_, value)| value.clone());
+}
+
+/// Parses the object number of an indirect reference (`12 0 R`) starting at `position`.
+fn parse_reference(pdf: &[u8], position: usize) -> Option<usize> {
+    let (id, id_digits) = parse_number(pdf, skip_whitespace(pdf, position))?;
+    let generation = skip_object(pdf, id_digits.end)?;
+    let keyword = skip_object(pdf, generation.end)?;
+    return (&pdf[keyword] == b"R").then_some(id);
+}
+
+/// Parses the classic cross-reference table (as written by `typst_pdf`). Returns byte offsets
+/// of in-use objects by their object number and the position of the trailer dictionary.
+fn parse_xref(pdf: &[u8]) -> Result<(HashMap<usize, usize>, usize), String> {
+    let startxref = rfind_bytes(pdf, b"startxref").ok_or("Missing `startxref`")?;
+    let digits_start = skip_whitespace(pdf, startxref + b"startxref".len());
+    let (xref, _) = parse_number(pdf, digits_start).ok_or("Invalid `startxref`")?;
+    if pdf.get(xref..xref + 4) != Some(b"xref") {
+        return Err(String::from("Cross-reference stream isn't supported"));
+    }
+
+    let mut offsets: HashMap<usize, usize> = HashMap::new();
+    let mut position = xref + 4;
+    loop {
+        position = skip_whitespace(pdf, position);
+        let Some((first, first_digits)) = parse_number(pdf, position) else {
+            break; // Reached the `trailer`.
+        };
+        position = first_digits.end + 1;
+        let (count, count_digits) = parse_number(pdf, position).ok_or("Invalid xref section")?;
+        position = skip_whitespace(pdf, count_digits.end);
+
+        for id in first..first + count {
+            let entry = pdf.get(position..position + 20).ok_or("Truncated xref entry")?;
+            if entry[17] == b'n' {
+                let (offset, _) = parse_number(pdf, position).ok_or("Invalid xref entry")?;
+                offsets.insert(id, offset);
+            }
+            position += 20;
+        }
+    }
+
+    if pdf.get(position..position + 7) != Some(b"trailer") {
+        return Err(String::from("Missing trailer"));
+    }
+    let trailer = skip_whitespace(pdf, position + 7);
+
+    return Ok((offsets, trailer));
+}
+
+/// Returns the position of the object `id` content (after `id 0 obj`), located by
+/// its cross-reference table offset.
+fn object_start(pdf: &[u8], offsets: &HashMap<usize, usize>, id: usize) -> Option<usize> {
+    let offset = *offsets.get(&id)?;
+    let (number, number_digits) = parse_number(pdf, offset)?;
+    let generation = skip_object(pdf, number_digits.end)?;
+    let keyword = skip_object(pdf, generation.end)?;
+    return (number == id && &pdf[keyword.clone()] == b"obj")
+        .then(|| skip_whitespace(pdf, keyword.end));
+}
+
+/// Sets the `/Producer` entry of the document information dictionary and, if XMP metadata
+/// is present, the matching `pdf:Producer` property (both are required to match for PDF/A).
+///
+/// Both are located through the trailer (`/Info`, `/Root` → `/Metadata`) and the
+/// cross-reference table, so document content (for example a title) can't be mistaken
+/// for them.
+///
+/// Returns modified PDF bytes, or an error message if the PDF structure is unexpected.
+pub(crate) fn set_producer(original: &[u8], producer: &str) -> Result<Vec<u8>, String> {
+    let pdf: &[u8] = original;
+    let (offsets, trailer) = parse_xref(pdf)?;
+    let (trailer_entries, _) = dictionary_entries(pdf, trailer).ok_or("Invalid trailer")?;
+
+    // Edits as (replaced range, new bytes), applied from the end of the file.
+    let mut edits: Vec<(Range<usize>, Vec<u8>)> = Vec::new();
+
+    // XMP metadata, `pdf:Producer` property is added after the creator tool.
+    let metadata_id: Option<usize> = dictionary_value(pdf, &trailer_entries, b"/Root")
+        .and_then(|root| parse_reference(pdf, root.start))
+        .and_then(|root_id| object_start(pdf, &offsets, root_id))
+        .and_then(|catalog| dictionary_entries(pdf, catalog))
+        .and_then(|(entries, _)| dictionary_value(pdf, &entries, b"/Metadata"))
+        .and_then(|metadata| parse_reference(pdf, metadata.start));
+
+    if let Some(metadata_id) = metadata_id {
+        let invalid = "Invalid XMP stream";
+        let stream = object_start(pdf, &offsets, metadata_id).ok_or(invalid)?;
+        let (entries, dictionary_end) = dictionary_entries(pdf, stream).ok_or(invalid)?;
+        let length_digits = dictionary_value(pdf, &entries, b"/Length").ok_or(invalid)?;
+        let (length, length_digits) = parse_number(pdf, length_digits.start).ok_or(invalid)?;
+
+        // Stream data starts after the `stream` keyword and its end of line.
+        let keyword = skip_object(pdf, dictionary_end + 2).ok_or(invalid)?;
+        if &pdf[keyword.clone()] != b"stream" {
+            return Err(String::from(invalid));
+        }
+        let data_start = match pdf.get(keyword.end..keyword.end + 2) {
+            Some(b"\r\n") => keyword.end + 2,
+            _ => keyword.end + 1
+        };
+        let data = pdf.get(data_start..data_start + length).ok_or(invalid)?;
+
+        // Existing property is replaced, otherwise it's added after the creator tool.
+        let existing: Option<Range<usize>> = find_bytes(data, b"<pdf:Producer>", 0)
+            .and_then(|start| {
+                let end = find_bytes(data, b"</pdf:Producer>", start)?;
+                Some(start..end + b"</pdf:Producer>".len())
+            });
+        let target: Option<Range<usize>> = existing.or_else(|| {
+            let end = find_bytes(data, b"</xmp:CreatorTool>", 0)? + b"</xmp:CreatorTool>".len();
+            Some(end..end)
+        });
+
+        // Compressed metadata is left alone.
+        let compressed = dictionary_value(pdf, &entries, b"/Filter").is_some();
+        if let (false, Some(target)) = (compressed, target) {
+            let escaped = producer
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;");
+            let property = format!("<pdf:Producer>{escaped}</pdf:Producer>");
+            let new_length = (length - target.len() + property.len()).to_string();
+
+            let target = data_start + target.start..data_start + target.end;
+            edits.push((target, property.into_bytes()));
+            edits.push((length_digits, new_length.into_bytes()));
+        }
+    }
+
+    // Document information dictionary, referenced from the trailer.
+    let info_id = dictionary_value(pdf, &trailer_entries, b"/Info")
+        .and_then(|info| parse_reference(pdf, info.start))
+        .ok_or("Missing document info")?;
+    let info = object_start(pdf, &offsets, info_id).ok_or("Invalid document info")?;
+    let (entries, dictionary_end) = dictionary_entries(pdf, info)
+        .ok_or("Invalid document info")?;
+
+    let producer_string = pdf_string(producer);
+    match dictionary_value(pdf, &entries, b"/Producer") {
+        Some(value) => edits.push((value, producer_string.into_bytes())),
+        None => {
+            let entry = format!("  /Producer {producer_string}\n");
+            edits.push((dictionary_end..dictionary_end, entry.into_bytes()));
+        }
+    }
+
+    // Edits shift only the bytes after them, so earlier positions stay valid.
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut pdf: Vec<u8> = original.to_vec();
+    for (range, bytes) in edits {
+        replace_range(&mut pdf, range, &bytes)?;
+    }
+
+    return Ok(pdf);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::CompilerBuilder;
+
+    /// Checks that the cross-reference offsets point to their objects and that stream data
+    /// ends exactly at `endstream`.
+    fn assert_structure(pdf: &[u8]) {
+        let (offsets, _) = parse_xref(pdf).expect("Invalid xref");
+        for (&id, &offset) in offsets.iter() {
+            let start = object_start(pdf, &offsets, id)
+                .unwrap_or_else(|| panic!("Object {id} isn't at offset {offset}"));
+
+            let Some((entries, end)) = dictionary_entries(pdf, start) else {
+                continue;
+            };
+            let Some(length) = dictionary_value(pdf, &entries, b"/Length") else {
+                continue;
+            };
+            let (length, _) = parse_number(pdf, length.start).unwrap();
+            let data_start = skip_object(pdf, end + 2).unwrap().end + 1;
+            let data_end = skip_whitespace(pdf, data_start + length);
+            assert!(pdf[data_end..].starts_with(b"endstream"), "Object {id} has wrong length");
+        }
+    }
+
+    #[test]
+    fn producer_ignores_document_content() {
+        let title = "</xmp:CreatorTool>";
+        let compiler = CompilerBuilder::with_content_input(format!(
+            "#set document(title: \"{title}\")\n{title}"
+        ))
+            .with_producer("Producer")
+            .build()
+            .expect("Couldn't build the compiler");
+        let pdf = compiler.compile_pdf().output.expect("Missing PDF");
+
+        assert_structure(&pdf);
+        let title_entry = format!("/Title ({title})\n");
+        assert!(find_bytes(&pdf, title_entry.as_bytes(), 0).is_some());
+        assert!(find_bytes(&pdf, b"/Producer (Producer)", 0).is_some());
+
+        let xmp = find_bytes(&pdf, b"<?xpacket begin", 0).expect("Missing XMP");
+        let property = b"</xmp:CreatorTool><pdf:Producer>Producer</pdf:Producer>";
+        assert!(find_bytes(&pdf, property, 0).is_some_and(|position| position > xmp));
+    }
+
+    #[test]
+    fn producer_replaces_existing_entry() {
+        let compiler = CompilerBuilder::with_content_input("Hello")
+            .build()
+            .expect("Couldn't build the compiler");
+        let pdf = compiler.compile_pdf().output.expect("Missing PDF");
+        let pdf = set_producer(&pdf, "Second (producer)").expect("Couldn't set the producer");
+
+        assert_structure(&pdf);
+        assert!(find_bytes(&pdf, b"/Producer (Second \\(producer\\))", 0).is_some());
+        assert!(find_bytes(&pdf, b"<pdf:Producer>Second (producer)</pdf:Producer>", 0).is_some());
+
+        let count = |needle: &[u8]| pdf.windows(needle.len()).filter(|x| x == &needle).count();
+        assert_eq!(count(b"/Producer "), 1);
+        assert_eq!(count(b"<pdf:Producer>"), 1);
+    }
+}
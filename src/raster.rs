@@ -0,0 +1,206 @@
+//! Provides helpers for processing rendered pages ([Pixmap]s) before encoding.
+//!
+//! ### Used internally.
+
+use tiny_skia::{IntRect, Pixmap, PixmapPaint, PremultipliedColorU8, Transform};
+use typst::layout::Size;
+use typst::visualize::Color;
+
+/// Converts typst [Color] to the pixel value it has when filled into a [Pixmap].
+///
+/// Fills a single pixel, the same way `typst_render` fills the page background,
+/// so the result is identical to the rendered background pixels.
+pub(crate) fn background_pixel(background: Color) -> Option<PremultipliedColorU8> {
+    let [r, g, b, a] = background.to_rgb().to_vec4_u8();
+
+    let mut probe = Pixmap::new(1, 1)?;
+    probe.fill(tiny_skia::Color::from_rgba8(r, g, b, a));
+    return probe.pixels().first().copied();
+}
+
+/// Computes pixel size of a page with `size` (in points) rendered with `ppi`, the same way
+/// `typst_render` does (rounded to nearest, at least 1 pixel).
+///
+/// Returns an error message if the `ppi` isn't a positive number or if a pixmap
+/// of that size can't be allocated (`typst_render` panics in that case).
+pub(crate) fn checked_pixel_size(size: Size, ppi: f32) -> Result<(u32, u32), String> {
+    if !(ppi.is_finite() && ppi > 0.0) {
+        return Err(format!("PPI must be a positive number, got {ppi}"));
+    }
+
+    let pixel_per_pt = ppi / 72.0;
+    let width = (pixel_per_pt * size.x.to_pt() as f32).round().max(1.0);
+    let height = (pixel_per_pt * size.y.to_pt() as f32).round().max(1.0);
+    let too_large = || format!("Page of {width}x{height} pixels (at {ppi} PPI) is too large");
+
+    // Row stride has to fit into `i32` and the data has to be allocatable.
+    if !(width <= (i32::MAX / 4) as f32 && height <= u32::MAX as f32) {
+        return Err(too_large());
+    }
+    let (width, height) = (width as u32, height as u32);
+    let length = (width as usize * 4).checked_mul(height as usize).ok_or_else(too_large)?;
+    Vec::<u8>::new().try_reserve_exact(length).map_err(|_| too_large())?;
+
+    return Ok((width, height));
+}
+
+/// Trims rows and columns that are entirely the `background` color.
+///
+/// - `padding`: Number of pixels of background left around the content,
+/// clamped to the `pixmap` edges.
+///
+/// If the whole `pixmap` is the `background` color (blank page), it's returned as is.
+pub(crate) fn autocrop(pixmap: Pixmap, background: Color, padding: u32) -> Pixmap {
+    let Some(background_pixel) = background_pixel(background) else {
+        return pixmap;
+    };
+
+    let width = pixmap.width();
+    let height = pixmap.height();
+    let pixels = pixmap.pixels();
+
+    // Content bounds (inclusive).
+    let mut min_x = u32::MAX;
+    let mut min_y = u32::MAX;
+    let mut max_x = 0;
+    let mut max_y = 0;
+
+    for y in 0..height {
+        let row_start = (y * width) as usize;
+        let row = &pixels[row_start..row_start + width as usize];
+
+        // Skips rows without content.
+        let Some(first) = row.iter().position(|&pixel| pixel != background_pixel) else {
+            continue;
+        };
+        let last = row.iter().rposition(|&pixel| pixel != background_pixel).unwrap_or(first);
+
+        min_x = min_x.min(first as u32);
+        max_x = max_x.max(last as u32);
+        min_y = min_y.min(y);
+        max_y = y;
+    }
+
+    // Blank page, nothing to crop to.
+    if min_x == u32::MAX {
+        return pixmap;
+    }
+
+    let left = min_x.saturating_sub(padding);
+    let top = min_y.saturating_sub(padding);
+    let right = max_x.saturating_add(padding).min(width - 1);
+    let bottom = max_y.saturating_add(padding).min(height - 1);
+
+    let rect = IntRect::from_xywh(
+        left as i32,
+        top as i32,
+        right - left + 1,
+        bottom - top + 1
+    );
+
+    return match rect.and_then(|rect| pixmap.clone_rect(rect)) {
+        Some(cropped) => cropped,
+        None => pixmap
+    };
+}
+
+/// Composes `pixmaps` into a grid with `columns` columns, filled with the `background` color.
+///
+/// Every pixmap is centered in a `cell_width` x `cell_height` cell, cells are separated
+/// by `gap` pixels. Pixmaps larger than the cell are clipped to the neighbouring cells.
+///
+/// Returns `None` if there are no `pixmaps` or the grid is too large.
+pub(crate) fn compose_grid(
+    pixmaps: &[Pixmap],
+    columns: usize,
+    cell_width: u32,
+    cell_height: u32,
+    gap: u32,
+    background: Color
+) -> Option<Pixmap> {
+    if pixmaps.is_empty() {
+        return None;
+    }
+
+    // Grid doesn't need more columns than pixmaps.
+    let columns = columns.clamp(1, pixmaps.len());
+    let rows = pixmaps.len().div_ceil(columns);
+
+    let grid_size = |cells: usize, cell: u32| -> Option<u32> {
+        let cells = u32::try_from(cells).ok()?;
+        return cell.checked_mul(cells)?.checked_add(gap.checked_mul(cells - 1)?);
+    };
+    let width = grid_size(columns, cell_width)?;
+    let height = grid_size(rows, cell_height)?;
+
+    let mut grid = Pixmap::new(width, height)?;
+    let [r, g, b, a] = background.to_rgb().to_vec4_u8();
+    grid.fill(tiny_skia::Color::from_rgba8(r, g, b, a));
+
+    for (index, pixmap) in pixmaps.iter().enumerate() {
+        let column = (index % columns) as u32;
+        let row = (index / columns) as u32;
+
+        let x = column * (cell_width + gap) + cell_width.saturating_sub(pixmap.width()) / 2;
+        let y = row * (cell_height + gap) + cell_height.saturating_sub(pixmap.height()) / 2;
+
+        grid.draw_pixmap(
+            x as i32,
+            y as i32,
+            pixmap.as_ref(),
+            &PixmapPaint::default(),
+            Transform::identity(),
+            None
+        );
+    }
+
+    return Some(grid);
+}
+
+/// Encodes `pixmap` as JPEG with `quality` (1 to 100).
+///
+/// JPEG has no alpha channel, so (semi-)transparent pixels are blended over white.
+#[cfg(feature = "jpeg_export")]
+pub(crate) fn encode_jpeg(pixmap: &Pixmap, quality: u8) -> Result<Vec<u8>, String> {
+    // Premultiplied color over white: `color * alpha + 255 * (1 - alpha)`.
+    let mut rgb: Vec<u8> = Vec::with_capacity(pixmap.pixels().len() * 3);
+    for pixel in pixmap.pixels() {
+        let white = 255 - pixel.alpha();
+        rgb.extend_from_slice(&[pixel.red() + white, pixel.green() + white, pixel.blue() + white]);
+    }
+
+    let mut buffer: Vec<u8> = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, quality)
+        .encode(&rgb, pixmap.width(), pixmap.height(), image::ExtendedColorType::Rgb8)
+        .map_err(|err| err.to_string())?;
+
+    return Ok(buffer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use typst::layout::Abs;
+
+    #[test]
+    fn pixel_size_rounds_to_nearest() {
+        let size = Size::new(Abs::pt(100.4), Abs::pt(100.6));
+        assert_eq!(checked_pixel_size(size, 72.0), Ok((100, 101)));
+        assert_eq!(checked_pixel_size(Size::zero(), 72.0), Ok((1, 1)));
+    }
+
+    #[test]
+    fn pixel_size_rejects_invalid_ppi() {
+        let size = Size::new(Abs::pt(100.0), Abs::pt(100.0));
+        for ppi in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(checked_pixel_size(size, ppi).is_err(), "PPI {ppi} accepted");
+        }
+    }
+
+    #[test]
+    fn pixel_size_rejects_unallocatable_pixmaps() {
+        let size = Size::new(Abs::pt(100.0), Abs::pt(100.0));
+        assert!(checked_pixel_size(size, 1e12).is_err());
+        assert!(checked_pixel_size(size, 3e9).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Provides a way to decompress [WOFF](https://www.w3.org/TR/WOFF/) web fonts
+//! into OpenType (TTF/OTF) font data, which typst can load.
+//!
+//! WOFF2 fonts are only detected, their decompression (Brotli with glyph table transforms)
+//! isn't supported.
+//!
+//! ### Used internally.
+
+use std::io::Read;
+
+/// Signature of a WOFF file.
+pub(crate) const WOFF_SIGNATURE: &[u8; 4] = b"wOFF";
+/// Signature of a WOFF2 file.
+pub(crate) const WOFF2_SIGNATURE: &[u8; 4] = b"wOF2";
+
+/// Size of the WOFF header in bytes.
+const HEADER_SIZE: usize = 44;
+/// Size of a WOFF table directory entry in bytes.
+const ENTRY_SIZE: usize = 20;
+/// Maximum compression ratio of zlib (deflate), used to limit preallocated buffers
+/// to what the input can actually produce.
+const MAX_ZLIB_RATIO: usize = 1032;
+
+/// Reads big-endian `u16` at `offset`.
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    return Some(u16::from_be_bytes([bytes[0], bytes[1]]));
+}
+
+/// Reads big-endian `u32` at `offset`.
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    return Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+}
+
+/// Decompresses WOFF font `data` into OpenType font data.
+///
+/// Tables are decompressed (zlib) if needed and written after a new table directory,
+/// each table aligned to 4 bytes. Extended metadata and private data are dropped.
+///
+/// Returns an error message if the `data` isn't a valid WOFF font.
+pub(crate) fn decode(data: &[u8]) -> Result<Vec<u8>, String> {
+    if !data.starts_with(WOFF_SIGNATURE) || data.len() < HEADER_SIZE {
+        return Err(String::from("not a WOFF font"));
+    }
+
+    let invalid = || String::from("invalid WOFF header");
+    let flavor = read_u32(data, 4).ok_or_else(invalid)?;
+    let tables_count = read_u16(data, 12).ok_or_else(invalid)?;
+    let sfnt_size = read_u32(data, 16).ok_or_else(invalid)?;
+
+    // Table directory has to fit into the data, before anything is allocated.
+    if HEADER_SIZE + tables_count as usize * ENTRY_SIZE > data.len() {
+        return Err(String::from("invalid WOFF table directory"));
+    }
+
+    // Binary search parameters of the table directory.
+    let entry_selector: u16 = tables_count.checked_ilog2().unwrap_or(0) as u16;
+    let search_range: u16 = (1u16 << entry_selector).saturating_mul(16);
+    let range_shift: u16 = tables_count.saturating_mul(16).saturating_sub(search_range);
+
+    let max_size = data.len().saturating_mul(MAX_ZLIB_RATIO);
+    let mut font: Vec<u8> = Vec::with_capacity((sfnt_size as usize).min(max_size));
+    font.extend_from_slice(&flavor.to_be_bytes());
+    font.extend_from_slice(&tables_count.to_be_bytes());
+    font.extend_from_slice(&search_range.to_be_bytes());
+    font.extend_from_slice(&entry_selector.to_be_bytes());
+    font.extend_from_slice(&range_shift.to_be_bytes());
+
+    // Table records are written first, tables follow after all records.
+    let records_end = 12 + 16 * tables_count as usize;
+    font.resize(records_end, 0);
+
+    for index in 0..tables_count as usize {
+        let entry = HEADER_SIZE + index * ENTRY_SIZE;
+        let invalid = || format!("invalid WOFF table entry {index}");
+
+        let tag = read_u32(data, entry).ok_or_else(invalid)?;
+        let offset = read_u32(data, entry + 4).ok_or_else(invalid)? as usize;
+        let compressed_length = read_u32(data, entry + 8).ok_or_else(invalid)? as usize;
+        let length = read_u32(data, entry + 12).ok_or_else(invalid)? as usize;
+        let checksum = read_u32(data, entry + 16).ok_or_else(invalid)?;
+
+        let stored = offset
+            .checked_add(compressed_length)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(invalid)?;
+        let table: Vec<u8> = if compressed_length < length {
+            let max_length = compressed_length.saturating_mul(MAX_ZLIB_RATIO);
+            let mut table: Vec<u8> = Vec::with_capacity(length.min(max_length));
+            flate2::read::ZlibDecoder::new(stored)
+                .take(length as u64)
+                .read_to_end(&mut table)
+                .map_err(|err| format!("couldn't decompress WOFF table {index}: {err}"))?;
+            table
+        } else {
+            stored.to_vec()
+        };
+        if table.len() != length {
+            return Err(format!("invalid WOFF table {index} length"));
+        }
+
+        let table_offset = font.len() as u32;
+        let record = 12 + 16 * index;
+        font[record..record + 4].copy_from_slice(&tag.to_be_bytes());
+        font[record + 4..record + 8].copy_from_slice(&checksum.to_be_bytes());
+        font[record + 8..record + 12].copy_from_slice(&table_offset.to_be_bytes());
+        font[record + 12..record + 16].copy_from_slice(&(length as u32).to_be_bytes());
+
+        // Tables are padded to 4 bytes.
+        font.extend_from_slice(&table);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+
+    return Ok(font);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a WOFF header with `tables_count` tables and `sfnt_size`, padded to `length`.
+    fn header(tables_count: u16, sfnt_size: u32, length: usize) -> Vec<u8> {
+        let mut data = vec![0u8; length.max(HEADER_SIZE)];
+        data[..4].copy_from_slice(WOFF_SIGNATURE);
+        data[4..8].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        data[12..14].copy_from_slice(&tables_count.to_be_bytes());
+        data[16..20].copy_from_slice(&sfnt_size.to_be_bytes());
+        return data;
+    }
+
+    /// Appends a table directory entry and the (uncompressed) `table` to the `data`.
+    fn push_table(data: &mut Vec<u8>, index: usize, tag: &[u8; 4], table: &[u8]) {
+        let entry = HEADER_SIZE + index * ENTRY_SIZE;
+        let offset = data.len() as u32;
+        let length = table.len() as u32;
+        data[entry..entry + 4].copy_from_slice(tag);
+        data[entry + 4..entry + 8].copy_from_slice(&offset.to_be_bytes());
+        data[entry + 8..entry + 12].copy_from_slice(&length.to_be_bytes());
+        data[entry + 12..entry + 16].copy_from_slice(&length.to_be_bytes());
+        data.extend_from_slice(table);
+    }
+
+    #[test]
+    fn rejects_table_directory_larger_than_data() {
+        let data = header(u16::MAX, 0, 64);
+        assert_eq!(decode(&data), Err(String::from("invalid WOFF table directory")));
+
+        let data = header(32768, 0, 64);
+        assert!(decode(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        let data = header(0, 0, HEADER_SIZE);
+        assert!(decode(&data[..HEADER_SIZE - 1]).is_err());
+        assert!(decode(b"OTTO").is_err());
+    }
+
+    #[test]
+    fn rejects_table_outside_of_data() {
+        let mut data = header(1, 0, HEADER_SIZE + ENTRY_SIZE);
+        push_table(&mut data, 0, b"head", b"data");
+        data[HEADER_SIZE + 4..HEADER_SIZE + 8].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(decode(&data), Err(String::from("invalid WOFF table entry 0")));
+    }
+
+    #[test]
+    fn ignores_huge_sfnt_size() {
+        let mut data = header(1, u32::MAX, HEADER_SIZE + ENTRY_SIZE);
+        push_table(&mut data, 0, b"head", b"abcdef");
+
+        let font = decode(&data).expect("Valid WOFF");
+        assert!(font.capacity() <= data.len() * MAX_ZLIB_RATIO);
+    }
+
+    #[test]
+    fn decodes_uncompressed_tables() {
+        let mut data = header(2, 0, HEADER_SIZE + 2 * ENTRY_SIZE);
+        push_table(&mut data, 0, b"cmap", b"abcdef");
+        push_table(&mut data, 1, b"head", b"1234");
+
+        let font = decode(&data).expect("Valid WOFF");
+        assert_eq!(&font[..4], &0x0001_0000u32.to_be_bytes());
+        assert_eq!(read_u16(&font, 4), Some(2)); // tables count
+        assert_eq!(read_u16(&font, 6), Some(32)); // search range
+        assert_eq!(read_u16(&font, 8), Some(1)); // entry selector
+        assert_eq!(read_u16(&font, 10), Some(0)); // range shift
+
+        // Tables follow the records, padded to 4 bytes.
+        let records_end = 12 + 2 * 16;
+        assert_eq!(&font[12..16], b"cmap");
+        assert_eq!(read_u32(&font, 20), Some(records_end as u32));
+        assert_eq!(&font[records_end..records_end + 6], b"abcdef");
+        assert_eq!(&font[28..32], b"head");
+        assert_eq!(read_u32(&font, 36), Some(records_end as u32 + 8));
+        assert_eq!(&font[records_end + 8..], b"1234");
+    }
+}